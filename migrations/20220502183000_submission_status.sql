@@ -0,0 +1,6 @@
+-- keep track of what happened to a submission so erased rows can leave a tombstone
+ALTER TABLE formanswers ADD COLUMN status TEXT NOT NULL DEFAULT 'pending';
+ALTER TABLE formanswers ADD COLUMN moderator_id BIGINT;
+ALTER TABLE formanswers ADD COLUMN submitted_at BIGINT;
+ALTER TABLE formanswers ADD COLUMN decided_at BIGINT;
+ALTER TABLE formanswers ADD COLUMN redacted_at BIGINT;
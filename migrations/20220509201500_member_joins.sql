@@ -0,0 +1,10 @@
+-- members we are waiting on to fill out the form
+CREATE TABLE member_joins (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    joined_at BIGINT NOT NULL,
+    reminded_at BIGINT,
+    -- waiting, reminded, kicked, verified, exempt, left
+    status TEXT NOT NULL DEFAULT 'waiting',
+    PRIMARY KEY (guild_id, user_id)
+);
@@ -0,0 +1,8 @@
+-- only one submission per member may wait for review at a time
+ALTER TABLE formanswers ADD COLUMN guild_id BIGINT;
+
+UPDATE formanswers SET status = 'superseded'
+WHERE status = 'pending'
+  AND rowid NOT IN (SELECT MAX(rowid) FROM formanswers WHERE status = 'pending' GROUP BY user_id);
+
+CREATE UNIQUE INDEX formanswers_active_submission ON formanswers (guild_id, user_id) WHERE status = 'pending';
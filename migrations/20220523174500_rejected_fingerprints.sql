@@ -0,0 +1,14 @@
+-- answers that don't map to a fixed choice, kept to recognise returning applicants
+ALTER TABLE formanswers ADD COLUMN free_text TEXT;
+
+-- what we remember about kicked or banned applicants, values are normalized
+CREATE TABLE rejected_fingerprints (
+    user_id BIGINT NOT NULL,
+    message_id BIGINT,
+    tag TEXT NOT NULL,
+    username TEXT NOT NULL,
+    display_name TEXT,
+    free_text TEXT,
+    reason TEXT NOT NULL,
+    rejected_at BIGINT NOT NULL
+);
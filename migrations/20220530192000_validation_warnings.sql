@@ -0,0 +1,2 @@
+-- contradictions found at intake, approval is blocked until a moderator resolves them
+ALTER TABLE formanswers ADD COLUMN validation_warnings TEXT;
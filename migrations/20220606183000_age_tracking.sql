@@ -0,0 +1,14 @@
+-- age at verification, used to move members into older brackets over time
+CREATE TABLE age_tracking (
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    message_id BIGINT NOT NULL,
+    age_at_verification BIGINT NOT NULL,
+    verified_at BIGINT NOT NULL,
+    -- minor, adult or senior
+    bracket TEXT NOT NULL,
+    is_female BOOL NOT NULL DEFAULT FALSE,
+    -- bracket the member was last asked to confirm, so they are only asked once per transition
+    asked_bracket TEXT,
+    PRIMARY KEY (guild_id, user_id)
+);
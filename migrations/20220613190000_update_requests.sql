@@ -0,0 +1,2 @@
+-- 'join' for new members, 'update' for verified members who submitted the form again
+ALTER TABLE formanswers ADD COLUMN kind TEXT NOT NULL DEFAULT 'join';
@@ -0,0 +1,21 @@
+-- roles the verification flow handed out, so they can be taken back again
+CREATE TABLE granted_roles (
+    message_id BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    role_id BIGINT NOT NULL,
+    granted_at BIGINT NOT NULL,
+    removed_at BIGINT
+);
+
+CREATE INDEX granted_roles_member ON granted_roles (guild_id, user_id);
+
+-- moderation actions taken through the bot
+CREATE TABLE audit_log (
+    created_at BIGINT NOT NULL,
+    guild_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    moderator_id BIGINT,
+    action TEXT NOT NULL,
+    details TEXT
+);
@@ -0,0 +1,2 @@
+-- moderator discussion thread on the review message
+ALTER TABLE formanswers ADD COLUMN thread_id BIGINT;
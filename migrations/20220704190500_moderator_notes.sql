@@ -0,0 +1,12 @@
+-- notes are keyed by user, so they are still there when someone leaves and joins again
+CREATE TABLE moderator_notes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id BIGINT,
+    user_id BIGINT NOT NULL,
+    message_id BIGINT,
+    moderator_id BIGINT NOT NULL,
+    note TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+CREATE INDEX moderator_notes_user ON moderator_notes (user_id);
@@ -0,0 +1,2 @@
+-- when the member last left, used to decide whether their roles are restored on rejoin
+ALTER TABLE member_joins ADD COLUMN left_at BIGINT;
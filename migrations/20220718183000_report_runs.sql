@@ -0,0 +1,5 @@
+-- when scheduled reports were last posted, so restarts don't skip or repeat them
+CREATE TABLE report_runs (
+    name TEXT PRIMARY KEY NOT NULL,
+    last_run_at BIGINT NOT NULL
+);
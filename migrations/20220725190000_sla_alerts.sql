@@ -0,0 +1,15 @@
+-- review time limits per guild, columns left NULL fall back to the environment defaults
+CREATE TABLE sla_settings (
+    guild_id BIGINT PRIMARY KEY NOT NULL,
+    alert_after_hours BIGINT,
+    alert_role_id BIGINT,
+    escalate_after_hours BIGINT,
+    escalate_role_id BIGINT
+);
+
+-- how far each pending submission was already escalated, 1 = alerted, 2 = escalated
+CREATE TABLE sla_alerts (
+    message_id BIGINT PRIMARY KEY NOT NULL,
+    level BIGINT NOT NULL,
+    alerted_at BIGINT NOT NULL
+);
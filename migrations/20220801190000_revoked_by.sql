@@ -0,0 +1,3 @@
+-- who revoked an approval and when, moderator_id and decided_at stay those of the approval
+ALTER TABLE formanswers ADD COLUMN revoked_by BIGINT;
+ALTER TABLE formanswers ADD COLUMN revoked_at BIGINT;
@@ -0,0 +1,7 @@
+-- rows without a guild slip through formanswers_active_submission, NULLs never conflict in a unique index
+UPDATE formanswers SET status = 'superseded'
+WHERE status = 'pending' AND guild_id IS NULL
+  AND (rowid NOT IN (SELECT MAX(rowid) FROM formanswers WHERE status = 'pending' AND guild_id IS NULL GROUP BY user_id)
+       OR user_id IN (SELECT user_id FROM formanswers WHERE status = 'pending' AND guild_id IS NOT NULL));
+
+CREATE UNIQUE INDEX formanswers_active_legacy_submission ON formanswers (user_id) WHERE status = 'pending' AND guild_id IS NULL;
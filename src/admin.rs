@@ -0,0 +1,136 @@
+use serenity::model::Timestamp;
+use sqlx::SqlitePool;
+
+use crate::bot::FormAnswersDB;
+use crate::cli::PurgeArgs;
+use crate::export::parse_date;
+use crate::structs::SubmissionStatus;
+
+fn format_timestamp(ts: Option<i64>) -> String {
+    ts.and_then(|ts| Timestamp::from_unix_timestamp(ts).ok())
+        .map(|ts| ts.to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+pub async fn show(pool: &SqlitePool, user_id: u64) -> Result<(), crate::Error> {
+    let uid = user_id as i64;
+    let submissions = sqlx::query_as!(
+        FormAnswersDB,
+        "SELECT * FROM formanswers WHERE user_id = ? ORDER BY submitted_at",
+        uid
+    )
+    .fetch_all(pool)
+    .await?;
+
+    if submissions.is_empty() {
+        println!("No submissions for user {}", user_id);
+        return Ok(());
+    }
+
+    for s in submissions.iter() {
+        println!("Submission {}", s.message_id);
+        println!("  kind:             {}", s.kind);
+        println!("  status:           {}", s.status);
+        println!("  submitted at:     {}", format_timestamp(s.submitted_at));
+        println!("  decided at:       {}", format_timestamp(s.decided_at));
+        println!(
+            "  decided by:       {}",
+            s.moderator_id.map(|id| id.to_string()).unwrap_or_else(|| "-".to_string())
+        );
+        if s.redacted_at.is_some() {
+            println!("  redacted at:      {}", format_timestamp(s.redacted_at));
+            continue;
+        }
+        println!("  gender:           {}", s.gender);
+        println!("  age:              {}", s.age.as_deref().unwrap_or("-"));
+        println!("  18+:              {}", s.is_18_plus);
+        println!("  30+:              {}", s.is_30_plus);
+        println!("  diagnosis status: {}", s.diagnosis_status.as_deref().unwrap_or("-"));
+    }
+
+    Ok(())
+}
+
+pub async fn set_status(pool: &SqlitePool, message_id: u64, status: SubmissionStatus) -> Result<(), crate::Error> {
+    let mid = message_id as i64;
+    let status = status.as_str();
+    let result = sqlx::query!("UPDATE formanswers SET status = ? WHERE message_id = ?", status, mid)
+        .execute(pool)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(format!("no submission with message ID {}", message_id).into());
+    }
+
+    // the review message is not touched, the bot is not connected to Discord here
+    eprintln!("Set status of {} to {}", message_id, status);
+    Ok(())
+}
+
+/// Submissions from before `submitted_at` was recorded are dated by the snowflake of their review message.
+pub async fn purge(pool: &SqlitePool, args: PurgeArgs) -> Result<(), crate::Error> {
+    let before = parse_date(&args.before, false)?;
+    let status = args.status.map(|s| s.as_str());
+
+    if args.dry_run {
+        let count = sqlx::query!(
+            "SELECT COUNT(*) AS count FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2)",
+            before,
+            status
+        )
+        .fetch_one(pool)
+        .await?
+        .count;
+        eprintln!("Would delete {} submission(s)", count);
+        return Ok(());
+    }
+
+    // rows that only make sense together with their submission go in the same transaction
+    let mut tx = pool.begin().await?;
+    sqlx::query!(
+        "DELETE FROM granted_roles WHERE message_id IN (SELECT message_id FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2))",
+        before,
+        status
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM moderator_notes WHERE message_id IN (SELECT message_id FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2))",
+        before,
+        status
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM age_tracking WHERE message_id IN (SELECT message_id FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2))",
+        before,
+        status
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM sla_alerts WHERE message_id IN (SELECT message_id FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2))",
+        before,
+        status
+    )
+    .execute(&mut tx)
+    .await?;
+    sqlx::query!(
+        "DELETE FROM rejected_fingerprints WHERE message_id IN (SELECT message_id FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2))",
+        before,
+        status
+    )
+    .execute(&mut tx)
+    .await?;
+    let result = sqlx::query!(
+        "DELETE FROM formanswers WHERE COALESCE(submitted_at, ((message_id >> 22) + 1420070400000) / 1000) < ?1 AND (?2 IS NULL OR status = ?2)",
+        before,
+        status
+    )
+    .execute(&mut tx)
+    .await?;
+    tx.commit().await?;
+    eprintln!("Deleted {} submission(s)", result.rows_affected());
+
+    Ok(())
+}
@@ -0,0 +1,421 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::{
+    client::Context,
+    http::HttpError,
+    model::{
+        id::{ChannelId, GuildId, RoleId},
+        interactions::{
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionResponseType,
+        },
+        Timestamp,
+    },
+    utils::Color,
+};
+use sqlx::SqlitePool;
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::shutdown::Coordinator;
+use crate::structs::{AgeTransitionSettings, GuildRoleSettings};
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const SECONDS_PER_YEAR: i64 = 31_557_600;
+/// Discord's JSON error code for a member that is not on the server.
+const UNKNOWN_MEMBER: isize = 10007;
+
+struct TrackedMember {
+    guild_id: i64,
+    user_id: i64,
+    message_id: i64,
+    age_at_verification: i64,
+    verified_at: i64,
+    bracket: String,
+    is_female: bool,
+    asked_bracket: Option<String>,
+}
+
+impl TrackedMember {
+    /// Only counts full years since verification, so nobody is moved before they actually crossed the threshold.
+    fn current_age(&self, now: i64) -> i64 {
+        self.age_at_verification + (now - self.verified_at) / SECONDS_PER_YEAR
+    }
+}
+
+pub fn bracket_for(age: i64) -> &'static str {
+    if age >= 30 {
+        "senior"
+    } else if age >= 18 {
+        "adult"
+    } else {
+        "minor"
+    }
+}
+
+fn describe_bracket(bracket: &str) -> &'static str {
+    match bracket {
+        "senior" => "30+",
+        "adult" => "18 to 29",
+        _ => "under 18",
+    }
+}
+
+fn is_unknown_member(why: &serenity::Error) -> bool {
+    matches!(why, serenity::Error::Http(e) if matches!(&**e, HttpError::UnsuccessfulRequest(r) if r.error.code == UNKNOWN_MEMBER))
+}
+
+/// Stops following the age of a member, for when they are no longer on the server.
+pub async fn untrack_age(pool: &SqlitePool, guild_id: i64, user_id: i64) {
+    if let Err(why) = sqlx::query!("DELETE FROM age_tracking WHERE guild_id = ? AND user_id = ?", guild_id, user_id)
+        .execute(pool)
+        .await
+    {
+        tracing::error!("Could not stop tracking the age of {}: {:?}", user_id, why);
+    }
+}
+
+impl Bot {
+    /// Remembers the age of an approved member so the age bracket roles can follow them.
+    pub async fn track_age(&self, guild_id: GuildId, submission: &FormAnswersDB) {
+        let age: i64 = match submission.age.as_deref().and_then(|a| a.parse().ok()) {
+            Some(a) => a,
+            None => return,
+        };
+
+        let bracket = match (submission.is_18_plus, submission.is_30_plus) {
+            (_, true) => "senior",
+            (true, false) => "adult",
+            _ => "minor",
+        };
+        // a moderator overrode the bracket, the age answer can't be trusted
+        if bracket_for(age) != bracket {
+            return;
+        }
+
+        let gid = guild_id.0 as i64;
+        let verified_at = Timestamp::now().unix_timestamp();
+        if let Err(why) = sqlx::query!(
+            "INSERT OR REPLACE INTO age_tracking (guild_id, user_id, message_id, age_at_verification, verified_at, bracket, is_female)
+            VALUES (?, ?, ?, ?, ?, ?, ?)",
+            gid,
+            submission.user_id,
+            submission.message_id,
+            age,
+            verified_at,
+            bracket,
+            submission.is_female
+        )
+        .execute(&self.database)
+        .await
+        {
+            tracing::error!("Could not track age of {}: {:?}", submission.user_id, why);
+        }
+    }
+
+    pub fn age_transition_job(&self) -> AgeTransitionJob {
+        AgeTransitionJob {
+            database: self.database.clone(),
+            shutdown: self.shutdown.clone(),
+            settings: self.age_transitions.clone(),
+            roles: self.roles.clone(),
+            log_channel: self.responses_channel,
+        }
+    }
+
+    /// Handles the buttons of the DM that asks a member to confirm their new bracket.
+    pub async fn age_transition_button(&self, ctx: &Context, msgc: &MessageComponentInteraction) {
+        let (confirmed, gid) = match msgc.data.custom_id.rsplit_once('_') {
+            Some(("age_transition_confirm", gid)) => (true, gid.parse::<i64>().unwrap_or_default()),
+            Some((_, gid)) => (false, gid.parse::<i64>().unwrap_or_default()),
+            None => return,
+        };
+        let uid = msgc.user.id.0 as i64;
+
+        let tracked = sqlx::query_as!(
+            TrackedMember,
+            "SELECT guild_id, user_id, message_id, age_at_verification, verified_at, bracket, is_female, asked_bracket
+            FROM age_tracking WHERE guild_id = ? AND user_id = ?",
+            gid,
+            uid
+        )
+        .fetch_optional(&self.database)
+        .await;
+
+        let text = match tracked {
+            Ok(Some(tracked)) if confirmed => {
+                let target = bracket_for(tracked.current_age(Timestamp::now().unix_timestamp()));
+                if target == tracked.bracket {
+                    "Your roles are already up to date.".to_string()
+                } else {
+                    match self.age_transition_job().apply(ctx, &tracked, target).await {
+                        Ok(()) => format!(
+                            "Thanks! Your roles now match the {} age group.",
+                            describe_bracket(target)
+                        ),
+                        Err(why) => {
+                            tracing::error!("Could not move {} to {}: {:?}", uid, target, why);
+                            "Your roles could not be updated, please contact a moderator."
+                                .to_string()
+                        }
+                    }
+                }
+            }
+            Ok(Some(_)) => "Okay, your roles stay as they are.".to_string(),
+            Ok(None) => "There is nothing to update anymore.".to_string(),
+            Err(why) => {
+                tracing::error!("Could not look up age tracking of {}: {:?}", uid, why);
+                "Something went wrong, please try again later.".to_string()
+            }
+        };
+
+        let _ = msgc
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::UpdateMessage);
+                f.interaction_response_data(|f| f.content(text).components(|c| c))
+            })
+            .await;
+    }
+}
+
+/// Moves members whose age group changed since verification into the roles of their new bracket.
+pub struct AgeTransitionJob {
+    database: SqlitePool,
+    shutdown: Arc<Coordinator>,
+    settings: AgeTransitionSettings,
+    roles: GuildRoleSettings,
+    log_channel: ChannelId,
+}
+
+impl AgeTransitionJob {
+    pub async fn run(self, ctx: Context) {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        while let Some(_running) = self.shutdown.tick(&mut interval).await {
+            if let Err(why) = self.sweep(&ctx).await {
+                tracing::error!("Could not update age brackets: {:?}", why);
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "age_transition_sweep", skip_all)]
+    async fn sweep(&self, ctx: &Context) -> Result<(), sqlx::Error> {
+        let tracked = sqlx::query_as!(
+            TrackedMember,
+            "SELECT guild_id, user_id, message_id, age_at_verification, verified_at, bracket, is_female, asked_bracket
+            FROM age_tracking WHERE bracket != 'senior'"
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        let now = Timestamp::now().unix_timestamp();
+        for member in tracked.iter() {
+            let target = bracket_for(member.current_age(now));
+            if target == member.bracket {
+                continue;
+            }
+
+            if !self.settings.require_confirmation {
+                if let Err(why) = self.apply(ctx, member, target).await {
+                    tracing::error!("Could not move {} to {}: {:?}", member.user_id, target, why);
+                }
+                continue;
+            }
+
+            if member.asked_bracket.as_deref() == Some(target) {
+                continue;
+            }
+            self.ask_confirmation(ctx, member, target).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn ask_confirmation(
+        &self,
+        ctx: &Context,
+        member: &TrackedMember,
+        target: &str,
+    ) -> Result<(), sqlx::Error> {
+        // only members still on the server are asked
+        let user = match ctx.http.get_member(member.guild_id as u64, member.user_id as u64).await {
+            Ok(m) => m.user,
+            Err(why) if is_unknown_member(&why) => {
+                untrack_age(&self.database, member.guild_id, member.user_id).await;
+                return Ok(());
+            }
+            Err(why) => {
+                tracing::error!("Could not look up {}: {:?}", member.user_id, why);
+                return Ok(());
+            }
+        };
+
+        let delivered = user
+            .direct_message(ctx, |m| {
+                m.content(format!(
+                    "Hi! Going by the age you gave when you were verified, you are now in the {} age group. \
+                    Should we update your roles on the server?",
+                    describe_bracket(target)
+                ));
+                m.components(|c| {
+                    c.create_action_row(|a| {
+                        a.create_button(|b| {
+                            b.label("Yes, update my roles");
+                            b.style(ButtonStyle::Success);
+                            b.custom_id(format!("age_transition_confirm_{}", member.guild_id));
+                            b
+                        });
+                        a.create_button(|b| {
+                            b.label("No, keep them");
+                            b.style(ButtonStyle::Secondary);
+                            b.custom_id(format!("age_transition_decline_{}", member.guild_id));
+                            b
+                        })
+                    })
+                })
+            })
+            .await
+            .is_ok();
+
+        sqlx::query!(
+            "UPDATE age_tracking SET asked_bracket = ? WHERE guild_id = ? AND user_id = ?",
+            target,
+            member.guild_id,
+            member.user_id
+        )
+        .execute(&self.database)
+        .await?;
+
+        let _ = self
+            .log_channel
+            .send_message(ctx, |f| {
+                f.embed(|e| {
+                    e.title("Age group change pending");
+                    e.description(format!(
+                        "<@{}> is now in the {} age group, asked them to confirm the role change.",
+                        member.user_id,
+                        describe_bracket(target)
+                    ));
+                    e.field("DM delivered", if delivered { "Yes" } else { "No" }, true);
+                    e.color(Color::ORANGE);
+                    e
+                })
+            })
+            .await;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(
+        skip_all,
+        fields(guild_id = member.guild_id, user_id = member.user_id, message_id = member.message_id, bracket = target)
+    )]
+    async fn apply(
+        &self,
+        ctx: &Context,
+        member: &TrackedMember,
+        target: &str,
+    ) -> Result<(), crate::Error> {
+        let mut add: Vec<RoleId> = Vec::new();
+        let mut remove: Vec<RoleId> = Vec::new();
+
+        if member.bracket == "minor" && target != "minor" {
+            remove.push(self.roles.fussvoelkchen);
+            add.push(self.roles.fussvolk);
+            if member.is_female {
+                remove.push(self.roles.f_child);
+                add.push(self.roles.f_adult);
+            }
+        }
+        if target == "senior" {
+            add.push(self.roles.boomer);
+        }
+
+        let mut discord_member = match ctx.http.get_member(member.guild_id as u64, member.user_id as u64).await {
+            Ok(m) => m,
+            Err(why) if is_unknown_member(&why) => {
+                // left while the bot was offline
+                untrack_age(&self.database, member.guild_id, member.user_id).await;
+                return Ok(());
+            }
+            Err(why) => return Err(why.into()),
+        };
+        let to_remove: Vec<RoleId> = remove
+            .iter()
+            .filter(|r| discord_member.roles.contains(r))
+            .copied()
+            .collect();
+        let to_add: Vec<RoleId> = add
+            .iter()
+            .filter(|r| !discord_member.roles.contains(r))
+            .copied()
+            .collect();
+        if !to_remove.is_empty() {
+            discord_member.remove_roles(ctx, &to_remove).await?;
+        }
+        if !to_add.is_empty() {
+            discord_member.add_roles(ctx, &to_add).await?;
+        }
+        crate::revoke::record_removed_roles(&self.database, member.guild_id, member.user_id, &to_remove).await?;
+        crate::revoke::record_granted_roles(
+            &self.database,
+            member.message_id,
+            member.guild_id,
+            member.user_id,
+            &to_add,
+        )
+        .await?;
+
+        sqlx::query!(
+            "UPDATE age_tracking SET bracket = ?, asked_bracket = NULL WHERE guild_id = ? AND user_id = ?",
+            target,
+            member.guild_id,
+            member.user_id
+        )
+        .execute(&self.database)
+        .await?;
+
+        tracing::info!(
+            "Moved {} from {} to {}",
+            discord_member.user.tag(),
+            member.bracket,
+            target
+        );
+        let _ = self
+            .log_channel
+            .send_message(ctx, |f| {
+                f.embed(|e| {
+                    e.title("Age group changed");
+                    e.description(format!(
+                        "<@{}> moved from {} to {}.",
+                        member.user_id,
+                        describe_bracket(&member.bracket),
+                        describe_bracket(target)
+                    ));
+                    e.field(
+                        "Added",
+                        add.iter()
+                            .map(|r| format!("<@&{}>", r.0))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        true,
+                    );
+                    if !remove.is_empty() {
+                        e.field(
+                            "Removed",
+                            remove
+                                .iter()
+                                .map(|r| format!("<@&{}>", r.0))
+                                .collect::<Vec<_>>()
+                                .join(" "),
+                            true,
+                        );
+                    }
+                    e.color(Color::DARK_GREEN);
+                    e
+                })
+            })
+            .await;
+
+        Ok(())
+    }
+}
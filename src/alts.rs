@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+
+use serenity::model::{guild::Member, Timestamp};
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::structs::SubmissionStatus;
+
+/// Free-text answers with fewer distinct words than this are too short to compare.
+const MIN_FREE_TEXT_WORDS: usize = 5;
+const MAX_REPORTED_ALTS: usize = 3;
+
+struct Fingerprint {
+    user_id: i64,
+    tag: String,
+    username: String,
+    display_name: Option<String>,
+    free_text: Option<String>,
+    reason: String,
+}
+
+pub struct PossibleAlt {
+    pub tag: String,
+    pub user_id: i64,
+    pub reason: String,
+    pub matched: &'static str,
+    pub similarity: f64,
+}
+
+impl PossibleAlt {
+    pub fn describe(&self) -> String {
+        format!(
+            "<@{}> was {}. {} is {:.0}% similar.",
+            self.user_id,
+            self.reason,
+            self.matched,
+            self.similarity * 100.0
+        )
+    }
+}
+
+/// Lowercases, drops the discriminator and undoes common character substitutions.
+pub fn normalize_name(name: &str) -> String {
+    let name = match name.rsplit_once('#') {
+        Some((name, discriminator)) if discriminator.chars().all(|c| c.is_ascii_digit()) => name,
+        _ => name,
+    };
+
+    name.to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' | '!' | '|' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            c => c,
+        })
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+pub fn normalize_text(text: &str) -> String {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// 1.0 for identical names, 0.0 for nothing in common.
+pub fn name_similarity(a: &str, b: &str) -> f64 {
+    let len = a.chars().count().max(b.chars().count());
+    if len == 0 {
+        return 0.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+/// Jaccard similarity of the distinct words of two normalized texts.
+pub fn text_similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<&str> = a.split(' ').collect();
+    let b: HashSet<&str> = b.split(' ').collect();
+    if a.len() < MIN_FREE_TEXT_WORDS || b.len() < MIN_FREE_TEXT_WORDS {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+impl Bot {
+    /// Remembers a rejected applicant so new accounts can be compared against them.
+    pub async fn record_fingerprint(&self, member: &Member, submission: &FormAnswersDB, reason: SubmissionStatus) {
+        let uid = member.user.id.0 as i64;
+        let tag = member.user.tag();
+        let username = normalize_name(&member.user.name);
+        let display_name = member.nick.as_deref().map(normalize_name);
+        let free_text = submission.free_text.as_deref().map(normalize_text);
+        let reason = reason.as_str();
+        let rejected_at = Timestamp::now().unix_timestamp();
+
+        if let Err(why) = sqlx::query!(
+            "INSERT INTO rejected_fingerprints (user_id, message_id, tag, username, display_name, free_text, reason, rejected_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            uid,
+            submission.message_id,
+            tag,
+            username,
+            display_name,
+            free_text,
+            reason,
+            rejected_at
+        )
+        .execute(&self.database)
+        .await
+        {
+            tracing::error!("Could not record fingerprint of {}: {:?}", tag, why);
+        }
+    }
+
+    /// Compares a new applicant against everyone we rejected before.
+    pub async fn find_possible_alts(&self, member: &Member, free_text: Option<&str>) -> Vec<PossibleAlt> {
+        let uid = member.user.id.0 as i64;
+        let fingerprints = match crate::metrics::db(
+            "load_fingerprints",
+            sqlx::query_as!(
+                Fingerprint,
+                "SELECT user_id, tag, username, display_name, free_text, reason FROM rejected_fingerprints WHERE user_id != ?",
+                uid
+            )
+            .fetch_all(&self.database),
+        )
+        .await
+        {
+            Ok(f) => f,
+            Err(why) => {
+                tracing::error!("Could not load fingerprints: {:?}", why);
+                return Vec::new();
+            }
+        };
+
+        let threshold = self.risk.alt_match_threshold;
+        let username = normalize_name(&member.user.name);
+        let display_name = member.nick.as_deref().map(normalize_name);
+        let free_text = free_text.map(normalize_text);
+
+        let mut alts: Vec<PossibleAlt> = Vec::new();
+        for fp in fingerprints.iter() {
+            let mut candidates = vec![("Username", name_similarity(&username, &fp.username))];
+            if let Some(old_display) = &fp.display_name {
+                candidates.push(("Username vs. their nickname", name_similarity(&username, old_display)));
+                if let Some(display) = &display_name {
+                    candidates.push(("Nickname", name_similarity(display, old_display)));
+                }
+            }
+            if let Some(display) = &display_name {
+                candidates.push(("Nickname vs. their username", name_similarity(display, &fp.username)));
+            }
+            if let (Some(text), Some(old_text)) = (&free_text, &fp.free_text) {
+                candidates.push(("Free-text answer", text_similarity(text, old_text)));
+            }
+
+            let best = candidates.into_iter().max_by(|a, b| a.1.total_cmp(&b.1));
+            if let Some((matched, similarity)) = best {
+                if similarity < threshold {
+                    continue;
+                }
+
+                // one entry per account, keep the strongest match
+                if let Some(existing) = alts.iter_mut().find(|a| a.user_id == fp.user_id) {
+                    if existing.similarity < similarity {
+                        existing.matched = matched;
+                        existing.similarity = similarity;
+                    }
+                    continue;
+                }
+
+                alts.push(PossibleAlt {
+                    tag: fp.tag.clone(),
+                    user_id: fp.user_id,
+                    reason: fp.reason.clone(),
+                    matched,
+                    similarity,
+                });
+            }
+        }
+
+        alts.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        alts.truncate(MAX_REPORTED_ALTS);
+        alts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_name_undoes_substitutions() {
+        assert_eq!(normalize_name("B0bby#1234"), "bobby");
+        assert_eq!(normalize_name("$t3v3_"), "steve");
+        assert_eq!(normalize_name("Name#abcd"), "nameabcd");
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn name_similarity_is_relative_to_length() {
+        assert_eq!(name_similarity("bobby", "bobby"), 1.0);
+        assert_eq!(name_similarity("", ""), 0.0);
+        assert!((name_similarity("bobby", "bobbi") - 0.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn text_similarity_needs_enough_words() {
+        assert_eq!(text_similarity("one two three", "one two three"), 0.0);
+
+        let a = normalize_text("I like trains, cats and long walks.");
+        let b = normalize_text("i like trains and cats and short walks");
+        // 6 shared words out of 8 distinct ones
+        assert!((text_similarity(&a, &b) - 0.75).abs() < 1e-9);
+    }
+}
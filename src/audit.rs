@@ -0,0 +1,31 @@
+use serenity::model::Timestamp;
+use sqlx::SqlitePool;
+
+/// Records a moderation action taken through the bot.
+pub async fn record(
+    pool: &SqlitePool,
+    guild_id: i64,
+    user_id: i64,
+    moderator_id: Option<i64>,
+    action: &str,
+    details: Option<&str>,
+) {
+    let created_at = Timestamp::now().unix_timestamp();
+    if let Err(why) = crate::metrics::db(
+        "audit_record",
+        sqlx::query!(
+            "INSERT INTO audit_log (created_at, guild_id, user_id, moderator_id, action, details) VALUES (?, ?, ?, ?, ?, ?)",
+            created_at,
+            guild_id,
+            user_id,
+            moderator_id,
+            action,
+            details
+        )
+        .execute(pool),
+    )
+    .await
+    {
+        tracing::error!("Could not record {} of {} in the audit log: {:?}", action, user_id, why);
+    }
+}
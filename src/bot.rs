@@ -1,40 +1,26 @@
-use serenity::client::bridge::gateway::event::ShardStageUpdateEvent;
-use serenity::json::Value;
-use serenity::model::channel::{Channel, ChannelCategory, GuildChannel, Reaction, StageInstance};
-use serenity::model::event::{
-    ChannelPinsUpdateEvent, GuildMemberUpdateEvent, GuildMembersChunkEvent, InviteCreateEvent,
-    InviteDeleteEvent, MessageUpdateEvent, ResumedEvent, ThreadListSyncEvent,
-    ThreadMembersUpdateEvent, TypingStartEvent, VoiceServerUpdateEvent,
-};
-use serenity::model::gateway::Presence;
-use serenity::model::guild::{
-    Emoji, Guild, Integration, PartialGuild, ThreadMember, UnavailableGuild,
-};
-use serenity::model::id::{ApplicationId, EmojiId, IntegrationId, MessageId, StickerId};
-use serenity::model::prelude::{CurrentUser, ReactionType, Sticker, User, VoiceState};
+use serenity::model::id::EmojiId;
+use serenity::model::prelude::{ReactionType, User};
+use serenity::model::Timestamp;
 use serenity::{
     async_trait,
+    builder::CreateEmbed,
     client::{Context, EventHandler},
     model::{
-        channel::{ChannelType, Message, PartialChannel, PartialGuildChannel},
+        channel::Message,
         gateway::Ready,
-        guild::{Member, Role},
-        id::{ChannelId, GuildId, RoleId, UserId},
+        guild::Member,
+        id::{ChannelId, GuildId, UserId},
         interactions::{
-            application_command::{
-                ApplicationCommandInteraction,
-                ApplicationCommandInteractionDataOptionValue as OptionValue,
-                ApplicationCommandOptionType, ApplicationCommandType,
-            },
             message_component::ButtonStyle,
             Interaction, InteractionApplicationCommandCallbackDataFlags,
         },
     },
-    prelude::Mentionable,
-    utils::{Color, MessageBuilder},
+    utils::Color,
 };
-use sqlx::{sqlite, FromRow, SqlitePool};
-use std::collections::HashMap;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::structs::SubmissionStatus;
 
 enum DiagnosisStatus {
     Formal,
@@ -56,10 +42,12 @@ struct FormAnswers {
     pub is_18_plus: bool,
     pub is_30_plus: bool,
     pub is_female: bool,
+    pub age: Option<String>,
+    pub free_text: Option<String>,
 }
 
 #[derive(Debug)]
-struct FormAnswersDB {
+pub(crate) struct FormAnswersDB {
     pub message_id: i64,
     pub user_id: i64,
     pub age: Option<String>,
@@ -68,21 +56,70 @@ struct FormAnswersDB {
     pub is_18_plus: bool,
     pub is_30_plus: bool,
     pub diagnosis_status: Option<String>,
+    pub status: String,
+    pub moderator_id: Option<i64>,
+    pub submitted_at: Option<i64>,
+    pub decided_at: Option<i64>,
+    pub redacted_at: Option<i64>,
+    pub guild_id: Option<i64>,
+    pub free_text: Option<String>,
+    pub validation_warnings: Option<String>,
+    pub kind: String,
+    pub thread_id: Option<i64>,
+    pub revoked_by: Option<i64>,
+    pub revoked_at: Option<i64>,
 }
 
 pub struct Bot {
     pub database: sqlx::SqlitePool,
     pub roles: crate::structs::GuildRoleSettings,
-    pub responses_channel: ChannelId
+    pub responses_channel: ChannelId,
+    pub welcome: crate::structs::WelcomeSettings,
+    pub grace: crate::structs::GracePeriodSettings,
+    pub risk: crate::structs::RiskSettings,
+    pub age_transitions: crate::structs::AgeTransitionSettings,
+    pub rejoin: crate::structs::RejoinSettings,
+    pub reports: crate::structs::ReportSettings,
+    pub sla: crate::structs::SlaSettings,
+    pub form_url: Option<String>,
+    pub jobs_started: AtomicBool,
+    pub shutdown: std::sync::Arc<crate::shutdown::Coordinator>,
 
 }
 
 #[async_trait]
 impl EventHandler for Bot {
-    async fn ready(&self, _: Context, ready: Ready) {
-        println!("Connected as {}", ready.user.name);
+    #[tracing::instrument(skip_all)]
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        tracing::info!("Connected as {}", ready.user.name);
+
+        if let Err(why) = crate::commands::register(&ctx).await {
+            tracing::error!("Could not register slash commands: {:?}", why);
+        }
+
+        // ready fires again after reconnects, only start the background jobs once
+        if !self.jobs_started.swap(true, Ordering::SeqCst) {
+            tokio::spawn(self.grace_period_job().run(ctx.clone()));
+            tokio::spawn(self.age_transition_job().run(ctx.clone()));
+            tokio::spawn(self.sla_job().run(ctx.clone()));
+            if let Some(job) = self.weekly_report_job() {
+                tokio::spawn(job.run(ctx.clone()));
+            }
+        }
     }
 
+    #[tracing::instrument(skip_all, fields(guild_id = new_member.guild_id.0, user_id = new_member.user.id.0))]
+    async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        // look at the previous stay before it is overwritten by the new join
+        self.returning_applicant(&ctx, &new_member).await;
+        self.track_join(&new_member).await;
+    }
+
+    #[tracing::instrument(skip_all, fields(guild_id = guild_id.0, user_id = user.id.0))]
     async fn guild_member_removal(
         &self,
         ctx: Context,
@@ -90,22 +127,40 @@ impl EventHandler for Bot {
         user: User,
         member_data_if_available: Option<Member>,
     ) {
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        self.track_leave(guild_id, user.id, member_data_if_available.as_ref()).await;
+        // also sent for kicks and bans
+        crate::aging::untrack_age(&self.database, guild_id.0 as i64, user.id.0 as i64).await;
+
         // lookup form answers if available
         // get message from db
         let uid = user.id.0 as i64;
-        let ee = sqlx::query_as!(
-            FormAnswersDB,
-            "SELECT * FROM formanswers WHERE user_id = ?",
-            uid
+        let ee = crate::metrics::db(
+            "latest_submission",
+            sqlx::query_as!(
+                FormAnswersDB,
+                "SELECT * FROM formanswers WHERE user_id = ? ORDER BY submitted_at DESC, rowid DESC LIMIT 1",
+                uid
+            )
+            .fetch_one(&self.database),
         )
-        .fetch_one(&self.database)
         .await;
 
-        match ee {
-            Ok(usr) => {
-                let mut msg = ctx.http.get_message(self.responses_channel.0, usr.message_id as u64).await.unwrap();
+        let usr = match ee {
+            Ok(usr) => usr,
+            Err(_) => {
+                tracing::debug!("Member left without a submission");
+                return;
+            }
+        };
 
-                msg.edit(&ctx, |f| {
+        // a deleted review message must not keep an external kick from being recorded
+        match ctx.http.get_message(self.responses_channel.0, usr.message_id as u64).await {
+            Ok(mut msg) => {
+                let edited = msg.edit(&ctx, |f| {
                     f.components(|c| {
                         c.create_action_row(|a| {
                             a.create_button(|b| {
@@ -116,21 +171,63 @@ impl EventHandler for Bot {
                             })
                         })
                     })
-                }).await.unwrap();
-            
-            },
-            Err(_) => {
-                println!("User didnt fill out form");
-                return;
+                }).await;
+                if let Err(why) = edited {
+                    tracing::warn!("Could not mark review message {}: {:?}", usr.message_id, why);
+                }
             }
+            Err(why) => tracing::warn!("Could not find review message {}: {:?}", usr.message_id, why),
         }
 
+        self.external_kick(&ctx, guild_id, &user).await;
+    }
+
+    #[tracing::instrument(skip_all, fields(guild_id = new.guild_id.0, user_id = new.user.id.0))]
+    async fn guild_member_update(&self, ctx: Context, old_if_available: Option<Member>, new: Member) {
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        self.manual_verification(&ctx, old_if_available.as_ref(), &new).await;
+    }
+
+    #[tracing::instrument(skip_all, fields(guild_id = guild_id.0, user_id = banned_user.id.0))]
+    async fn guild_ban_addition(&self, ctx: Context, guild_id: GuildId, banned_user: User) {
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        self.external_ban(&ctx, guild_id, &banned_user).await;
     }
 
+    #[tracing::instrument(skip_all, fields(guild_id = guild_id.0, user_id = unbanned_user.id.0))]
+    async fn guild_ban_removal(&self, ctx: Context, guild_id: GuildId, unbanned_user: User) {
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        self.external_unban(&ctx, guild_id, &unbanned_user).await;
+    }
+
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            guild_id = msg.guild_id.map(|g| g.0).unwrap_or_default(),
+            webhook_message_id = msg.id.0,
+            user_id = tracing::field::Empty,
+            message_id = tracing::field::Empty,
+        )
+    )]
     async fn message(&self, ctx: Context, msg: Message) {
         if msg.author.id != serenity::model::prelude::UserId(968523052247818382) {
             return; // only listen to our webhook
         }
+        // while shutting down the webhook message is left in the channel untouched
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        crate::metrics::SUBMISSIONS_RECEIVED.inc();
 
         // clone embed
         let embed = &msg.embeds[0];
@@ -141,29 +238,33 @@ impl EventHandler for Bot {
             fields.push(field.clone());
         }
 
-        let answers = parse_form_answers(fields.clone()).await.unwrap();
-
-        let users_matching_user = msg
-            .guild(&ctx)
-            .unwrap()
-            .search_members(&ctx, &answers.discord_tag, Some(100))
-            .await
-            .unwrap();
-
-        // find correct user
-        let mut user_id = None;
-        for member in users_matching_user.iter() {
-            if answers.discord_tag.contains(&member.user.name.as_str())
-                && !member.roles.contains(&self.roles.default_member_role)
-            {
-                user_id = Some(member.user.id);
-                break;
+        let answers = match parse_form_answers(fields.clone()).await {
+            Ok(a) => a,
+            Err(why) => {
+                crate::metrics::PARSE_FAILURES.inc();
+                tracing::error!("Could not read form submission {}: {}", msg.id, why);
+                return;
             }
-        }
+        };
 
-        let uid = match user_id {
-            Some(id) => id,
+        let users_matching_user = crate::metrics::discord(
+            "search_members",
+            msg.guild(&ctx).unwrap().search_members(&ctx, &answers.discord_tag, Some(100)),
+        )
+        .await
+        .unwrap();
+
+        // find correct user, verified members submitting again are asking for an update
+        let candidates: Vec<(String, String, bool)> = users_matching_user
+            .iter()
+            .map(|m| (m.user.name.clone(), m.user.tag(), m.roles.contains(&self.roles.default_member_role)))
+            .collect();
+        let matched_member = match_member(&answers.discord_tag, &candidates).map(|i| &users_matching_user[i]);
+
+        let member = match matched_member {
+            Some(m) => m,
             None => {
+                crate::metrics::MATCH_FAILURES.inc();
                 msg.channel_id.send_message(&ctx, |f| {
                     f.embed(|e| {
                         e.title("New Submission");
@@ -178,50 +279,104 @@ impl EventHandler for Bot {
                 return;
             }
         };
+        let uid = member.user.id;
+        tracing::Span::current().record("user_id", uid.0);
+        let risk = self.assess_risk(member).await;
+        let alts = self.find_possible_alts(member, answers.free_text.as_deref()).await;
+        let notes = self.notes_for(uid.0 as i64).await;
+        let warnings = crate::validation::validate_answers(answers.is_18_plus, answers.is_30_plus, answers.age.as_deref());
+
+        // a usable age answer decides the bracket, the yes/no questions are only a fallback
+        let age = answers.age.as_deref().and_then(crate::validation::parse_age);
+        let (is_18_plus, is_30_plus) = match age {
+            Some(age) => crate::validation::age_brackets(age),
+            None => (answers.is_18_plus, answers.is_30_plus),
+        };
+
+        let d = match answers.status {
+            DiagnosisStatus::Formal => "Formal",
+            DiagnosisStatus::Questioning => "Questioning",
+            DiagnosisStatus::SelfDiagnose => "Self Diagnosed",
+            DiagnosisStatus::FriendOrFamily => "Family Member or Friend of an Autistic Individual.",
+        };
 
-        let new_msg = msg
+        let is_update = member.roles.contains(&self.roles.default_member_role);
+        let role_diff = is_update.then(|| {
+            let wanted = self.roles.for_answers(answers.is_female, is_18_plus, is_30_plus, d);
+            crate::reverify::RoleDiff::new(&member.roles, &wanted, &self.roles.managed())
+        });
+
+        let new_msg = crate::metrics::discord("send_review", msg
             .channel_id
             .send_message(&ctx, |f| {
                 f.content(format!("User Mention: <@{}>", uid));
                 f.embed(|e| {
-                    e.title("New Form Submission");
-                    e.color(Color::BLURPLE);
+                    e.title(if is_update { "Update Request" } else { "New Form Submission" });
+                    e.color(risk.color(&self.risk));
                     e.fields(
                         fields
                             .iter()
                             .map(|f| (f.name.clone(), f.value.clone(), false)),
                     );
+                    risk.add_fields(e, &self.risk);
+                    for alt in alts.iter() {
+                        e.field(format!("Possible alt of {}", alt.tag), alt.describe(), false);
+                    }
+                    if !notes.is_empty() {
+                        e.field(crate::notes::NOTES_FIELD, crate::notes::format_notes(&notes), false);
+                    }
+                    if let Some(diff) = &role_diff {
+                        diff.add_field(e);
+                    }
+                    if let Some(age) = age {
+                        e.field(
+                            "Age bracket",
+                            format!(
+                                "{} (from age answer {})",
+                                match (is_18_plus, is_30_plus) {
+                                    (_, true) => "30+",
+                                    (true, false) => "18 to 29",
+                                    _ => "under 18",
+                                },
+                                age
+                            ),
+                            true,
+                        );
+                    }
+                    if !warnings.is_empty() {
+                        e.field(
+                            crate::validation::WARNING_FIELD,
+                            format!("{}\nSet the age bracket below before approving.", warnings.join("\n")),
+                            false,
+                        );
+                    }
                     e.footer(|f| {
                         f.text(format!("Gotten UserId {}", uid));
                         f
                     });
+                    crate::embeds::fit(e);
                     e
                 });
                 f.components(|c| {
-                    c.create_action_row(|a| {
-                        a.create_button(|b| {
-                            b.label("Accept");
-                            b.style(ButtonStyle::Success);
-                            b.custom_id("approve_user");
-                            b
-                        });
-                        a.create_button(|b| {
-                            b.label("Deny & Ban");
-                            b.style(ButtonStyle::Danger);
-                            b.custom_id("reject_user_and_ban");
-                            b
-                        });
-                        a.create_button(|b| {
-                            b.label("Deny & Kick");
-                            b.style(ButtonStyle::Danger);
-                            b.custom_id("reject_user_and_kick");
-                            b
-                        })
-                    })
+                    if is_update {
+                        crate::reverify::update_components(c, !warnings.is_empty())
+                    } else {
+                        crate::validation::review_components(c, !warnings.is_empty())
+                    }
                 })
-            })
-            .await
-            .unwrap();
+            }))
+            .await;
+        // the form message stays in the channel, so the submission isn't lost
+        let new_msg = match new_msg {
+            Ok(m) => m,
+            Err(why) => {
+                tracing::error!("Could not post submission of {} for review: {:?}", answers.discord_tag, why);
+                return;
+            }
+        };
+
+        tracing::Span::current().record("message_id", new_msg.id.0);
+        tracing::info!("Posted {} for review", if is_update { "update request" } else { "submission" });
 
         // delete trigger message
         msg.delete(&ctx).await.unwrap();
@@ -232,29 +387,96 @@ impl EventHandler for Bot {
             Gender::Divers => "Other",
         };
 
-        let d = match answers.status {
-            DiagnosisStatus::Formal => "Formal",
-            DiagnosisStatus::Questioning => "Questioning",
-            DiagnosisStatus::SelfDiagnose => "Self Diagnosed",
-            DiagnosisStatus::FriendOrFamily => "Family Member or Friend of an Autistic Individual.",
-            _ => unimplemented!("Sag wallah"),
-        };
-
         let n_msgid = new_msg.id.0 as i64;
         let n_uid = uid.0 as i64;
-
-        // save to db
+        let n_gid = msg.guild_id.map(|g| g.0 as i64);
+        let submitted_at = new_msg.timestamp.unix_timestamp();
+        let age = age.map(|a| a.to_string());
+        let validation_warnings = if warnings.is_empty() { None } else { Some(warnings.join("\n")) };
+        let kind = if is_update { "update" } else { "join" };
+
+        let previous = self.active_submissions(n_gid, n_uid).await;
+
+        // save to db, replacing any submission that is still waiting for review
+        let timer = crate::metrics::DB_QUERY_SECONDS.with_label_values(&["insert_submission"]).start_timer();
+        let mut tx = self.database.begin().await.unwrap();
+        sqlx::query!(
+            "UPDATE formanswers SET status = 'superseded' WHERE (guild_id = ?1 OR guild_id IS NULL) AND user_id = ?2 AND status = 'pending'",
+            n_gid, n_uid
+        )
+        .execute(&mut tx)
+        .await.unwrap();
         let _ = sqlx::query!(
-            "INSERT INTO formanswers (message_id, user_id, gender, is_female, is_18_plus, is_30_plus, diagnosis_status) VALUES (?, ?, ?, ?, ?, ?, ?)",
-            n_msgid, n_uid, g, answers.is_female, answers.is_18_plus, answers.is_30_plus, d
+            "INSERT INTO formanswers (message_id, user_id, age, gender, is_female, is_18_plus, is_30_plus, diagnosis_status, submitted_at, guild_id, free_text, validation_warnings, kind) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            n_msgid, n_uid, age, g, answers.is_female, is_18_plus, is_30_plus, d, submitted_at, n_gid, answers.free_text, validation_warnings, kind
 
         )
-        .execute(&self.database)
+        .execute(&mut tx)
         .await.unwrap();
+        tx.commit().await.unwrap();
+        timer.observe_duration();
+
+        self.open_review_thread(&ctx, &new_msg, &member.user.tag()).await;
+
+        if !previous.is_empty() {
+            self.mark_superseded(&ctx, &previous, &new_msg).await;
+        }
     }
 
+    #[tracing::instrument(
+        skip_all,
+        fields(
+            guild_id = tracing::field::Empty,
+            moderator = tracing::field::Empty,
+            message_id = tracing::field::Empty,
+            user_id = tracing::field::Empty,
+            action = tracing::field::Empty,
+        )
+    )]
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let _running = match self.shutdown.enter() {
+            Some(r) => r,
+            None => return,
+        };
+        record_interaction(&interaction);
+
+        if let Interaction::ApplicationCommand(cmd) = &interaction {
+            crate::commands::dispatch(self, &ctx, cmd).await;
+            return;
+        }
+        if let Interaction::ModalSubmit(modal) = &interaction {
+            if modal.data.custom_id == "add_note_modal" {
+                self.note_modal(&ctx, modal).await;
+            }
+            return;
+        }
+
         if let Interaction::MessageComponent(mut msgc) = interaction {
+            if msgc.data.custom_id == "add_note" {
+                self.add_note_button(&ctx, &msgc).await;
+                return;
+            }
+            if msgc.data.custom_id.starts_with("rejoin_") {
+                self.rejoin_button(&ctx, &msgc).await;
+                return;
+            }
+            if msgc.data.custom_id.starts_with("forget_me_") {
+                self.forget_me_button(&ctx, &msgc).await;
+                return;
+            }
+            if msgc.data.custom_id.starts_with("age_transition_") {
+                self.age_transition_button(&ctx, &msgc).await;
+                return;
+            }
+            if msgc.data.custom_id == "approve_update" || msgc.data.custom_id == "reject_update" {
+                self.update_request_button(&ctx, &msgc).await;
+                return;
+            }
+            if msgc.data.custom_id.starts_with("resolve_bracket_") {
+                self.resolve_bracket_button(&ctx, &mut msgc).await;
+                return;
+            }
+
             let intaraction_message_id = msgc.message.id.0 as i64;
 
             if msgc.data.custom_id == "approve_user" {
@@ -264,17 +486,20 @@ impl EventHandler for Bot {
                 }).await;
 
                 // get message from db
-                let ee = sqlx::query_as!(
-                    FormAnswersDB,
-                    "SELECT * FROM formanswers WHERE message_id = ?",
-                    intaraction_message_id
+                let ee = crate::metrics::db(
+                    "find_submission",
+                    sqlx::query_as!(
+                        FormAnswersDB,
+                        "SELECT * FROM formanswers WHERE message_id = ?",
+                        intaraction_message_id
+                    )
+                    .fetch_one(&self.database),
                 )
-                .fetch_one(&self.database)
                 .await;
 
                 let frm = match ee {
                     Ok(m) => m,
-                    Err(e) => {
+                    Err(_) => {
                         msgc.edit_original_interaction_response(&ctx, |f| {
                             f.embed(|e| {
                                 e.title("Error");
@@ -290,52 +515,49 @@ impl EventHandler for Bot {
                     }
                 };
 
-                // match roles
-                let mut roles = Vec::new();
-
-                roles.push(self.roles.default_member_role);
-
-                if frm.is_18_plus {
-                    roles.push(self.roles.fussvolk);
-                }
-                if frm.is_30_plus {
-                    roles.push(self.roles.boomer);
-                }
-
-                if !frm.is_18_plus && !frm.is_30_plus {
-                    roles.push(self.roles.fussvoelkchen);
-                }
-
-                if frm.is_female && !frm.is_18_plus && !frm.is_30_plus {
-                    roles.push(self.roles.f_child);
-                }
-
-                if frm.is_female && frm.is_18_plus {
-                    roles.push(self.roles.f_adult);
-                }
-
-                if frm.is_female && frm.is_30_plus {
-                    roles.push(self.roles.f_adult);
+                if let Some(warnings) = &frm.validation_warnings {
+                    let _ = msgc.edit_original_interaction_response(&ctx, |f| {
+                        f.embed(|e| {
+                            e.title("Error");
+                            e.description(format!("Resolve the age bracket before approving:\n{}", warnings));
+                            e.color(Color::DARK_RED);
+                            e
+                        });
+                        f
+                    })
+                    .await;
+                    return;
                 }
 
-                match frm.diagnosis_status.unwrap().as_str() {
-                    "Family Member or Friend of an Autistic Individual." => {
-                        roles.push(self.roles.non_asd_role);
-                    }
-                    _ => {
-                        roles.push(self.roles.asd_role);
-                    }
-                }
+                tracing::Span::current().record("user_id", frm.user_id);
+                let roles = self.roles.for_answers(
+                    frm.is_female,
+                    frm.is_18_plus,
+                    frm.is_30_plus,
+                    frm.diagnosis_status.as_deref().unwrap_or_default(),
+                );
 
                 // add user to roles
                 let usr = UserId(frm.user_id as u64);
-                let mut mem = ctx
-                    .http
-                    .get_member(msgc.guild_id.unwrap().0, usr.0)
+                let mut mem = crate::metrics::discord("get_member", ctx.http.get_member(msgc.guild_id.unwrap().0, usr.0))
                     .await
                     .unwrap();
+                let mut granted = Vec::new();
                 for role in roles {
-                    let _ = mem.add_role(&ctx, role).await;
+                    match crate::metrics::discord("add_role", mem.add_role(&ctx, role)).await {
+                        Ok(()) => granted.push(role),
+                        Err(why) => {
+                            crate::metrics::ROLE_ASSIGNMENT_ERRORS.inc();
+                            tracing::error!("Could not give role {} to {}: {:?}", role, mem.user.tag(), why);
+                        }
+                    }
+                }
+
+                self.record_decision(&ctx, intaraction_message_id, SubmissionStatus::Approved, msgc.user.id).await;
+                self.track_age(msgc.guild_id.unwrap(), &frm).await;
+                let gid = msgc.guild_id.unwrap().0 as i64;
+                if let Err(why) = crate::revoke::record_granted_roles(&self.database, frm.message_id, gid, frm.user_id, &granted).await {
+                    tracing::error!("Could not record granted roles of {}: {:?}", frm.user_id, why);
                 }
 
                 let _ = msgc
@@ -384,6 +606,13 @@ impl EventHandler for Bot {
                         })
                     })
                     .await;
+
+                if !self.welcome_member(&ctx, msgc.guild_id.unwrap(), &mem.user, &granted).await {
+                    let mut embed = CreateEmbed::from(msgc.message.embeds[0].clone());
+                    embed.field("Welcome DM", "Could not be delivered, the member does not accept DMs", false);
+                    crate::embeds::fit(&mut embed);
+                    let _ = msgc.message.edit(&ctx, |f| f.set_embed(embed)).await;
+                }
             } else if msgc.data.custom_id == "reject_user_and_ban" {
                 let _ = msgc.create_interaction_response(&ctx, |f| {
                     f.kind(serenity::model::interactions::InteractionResponseType::DeferredChannelMessageWithSource);
@@ -391,17 +620,20 @@ impl EventHandler for Bot {
                 }).await;
 
                 // get message from db
-                let ee = sqlx::query_as!(
-                    FormAnswersDB,
-                    "SELECT * FROM formanswers WHERE message_id = ?",
-                    intaraction_message_id
+                let ee = crate::metrics::db(
+                    "find_submission",
+                    sqlx::query_as!(
+                        FormAnswersDB,
+                        "SELECT * FROM formanswers WHERE message_id = ?",
+                        intaraction_message_id
+                    )
+                    .fetch_one(&self.database),
                 )
-                .fetch_one(&self.database)
                 .await;
 
                 let frm = match ee {
                     Ok(m) => m,
-                    Err(e) => {
+                    Err(_) => {
                         msgc.edit_original_interaction_response(&ctx, |f| {
                             f.embed(|e| {
                                 e.title("Error");
@@ -417,13 +649,15 @@ impl EventHandler for Bot {
                     }
                 };
 
+                tracing::Span::current().record("user_id", frm.user_id);
                 let usr = UserId(frm.user_id as u64);
-                let mem = ctx
-                    .http
-                    .get_member(msgc.guild_id.unwrap().0, usr.0)
+                let mem = crate::metrics::discord("get_member", ctx.http.get_member(msgc.guild_id.unwrap().0, usr.0))
                     .await
                     .unwrap();
-                mem.ban(&ctx, 0).await.unwrap();
+                self.record_fingerprint(&mem, &frm, SubmissionStatus::Banned).await;
+                crate::metrics::discord("ban", mem.ban(&ctx, 0)).await.unwrap();
+
+                self.record_decision(&ctx, intaraction_message_id, SubmissionStatus::Banned, msgc.user.id).await;
 
                 let _ = msgc
                     .message
@@ -478,17 +712,20 @@ impl EventHandler for Bot {
                 }).await;
 
                 // get message from db
-                let ee = sqlx::query_as!(
-                    FormAnswersDB,
-                    "SELECT * FROM formanswers WHERE message_id = ?",
-                    intaraction_message_id
+                let ee = crate::metrics::db(
+                    "find_submission",
+                    sqlx::query_as!(
+                        FormAnswersDB,
+                        "SELECT * FROM formanswers WHERE message_id = ?",
+                        intaraction_message_id
+                    )
+                    .fetch_one(&self.database),
                 )
-                .fetch_one(&self.database)
                 .await;
 
                 let frm = match ee {
                     Ok(m) => m,
-                    Err(e) => {
+                    Err(_) => {
                         msgc.edit_original_interaction_response(&ctx, |f| {
                             f.embed(|e| {
                                 e.title("Error");
@@ -504,13 +741,15 @@ impl EventHandler for Bot {
                     }
                 };
 
+                tracing::Span::current().record("user_id", frm.user_id);
                 let usr = UserId(frm.user_id as u64);
-                let mem = ctx
-                    .http
-                    .get_member(msgc.guild_id.unwrap().0, usr.0)
+                let mem = crate::metrics::discord("get_member", ctx.http.get_member(msgc.guild_id.unwrap().0, usr.0))
                     .await
                     .unwrap();
-                mem.kick(&ctx).await.unwrap();
+                self.record_fingerprint(&mem, &frm, SubmissionStatus::Kicked).await;
+                crate::metrics::discord("kick", mem.kick(&ctx)).await.unwrap();
+
+                self.record_decision(&ctx, intaraction_message_id, SubmissionStatus::Kicked, msgc.user.id).await;
 
                 let _ = msgc
                     .message
@@ -563,22 +802,90 @@ impl EventHandler for Bot {
     }
 }
 
+/// Fills the interaction span with who did what where.
+fn record_interaction(interaction: &Interaction) {
+    let span = tracing::Span::current();
+    let (guild_id, moderator, action) = match interaction {
+        Interaction::ApplicationCommand(cmd) => (cmd.guild_id, cmd.user.id, cmd.data.name.as_str()),
+        Interaction::MessageComponent(msgc) => {
+            span.record("message_id", msgc.message.id.0);
+            (msgc.guild_id, msgc.user.id, msgc.data.custom_id.as_str())
+        }
+        Interaction::ModalSubmit(modal) => {
+            if let Some(message) = &modal.message {
+                span.record("message_id", message.id.0);
+            }
+            (modal.guild_id, modal.user.id, modal.data.custom_id.as_str())
+        }
+        _ => return,
+    };
+    span.record("guild_id", guild_id.map(|g| g.0).unwrap_or_default());
+    span.record("moderator", moderator.0);
+    span.record("action", action);
+}
+
+impl Bot {
+    #[tracing::instrument(skip_all, fields(message_id = message_id, status = %status, moderator = moderator.0))]
+    pub(crate) async fn record_decision(&self, ctx: &Context, message_id: i64, status: SubmissionStatus, moderator: UserId) {
+        let summary = format!("Submission {} by <@{}>", status, moderator.0);
+        let status = status.as_str();
+        let moderator_id = moderator.0 as i64;
+        let decided_at = Timestamp::now().unix_timestamp();
+
+        match crate::metrics::db(
+            "record_decision",
+            sqlx::query!(
+                "UPDATE formanswers SET status = ?, moderator_id = ?, decided_at = ? WHERE message_id = ?",
+                status, moderator_id, decided_at, message_id
+            )
+            .execute(&self.database),
+        )
+        .await
+        {
+            Ok(_) => crate::metrics::DECISIONS.with_label_values(&[status]).inc(),
+            Err(why) => tracing::error!("Could not record decision for {}: {:?}", message_id, why),
+        }
+        tracing::info!("{}", summary);
+
+        self.close_review_thread(ctx, message_id, &summary).await;
+    }
+}
+
+/// Picks which of the `(username, tag, verified)` search results a submitted Discord tag belongs to.
+///
+/// Exact matches come first, unverified members before verified ones. A partial match is only
+/// good enough for an unverified member, "bobby#1234" must not update a verified "bob".
+fn match_member(submitted: &str, candidates: &[(String, String, bool)]) -> Option<usize> {
+    let submitted = submitted.trim();
+    let exact = |(name, tag, _): &(String, String, bool)| tag == submitted || name == submitted;
+    let partial = |(name, _, _): &(String, String, bool)| submitted.contains(name.as_str());
+
+    candidates
+        .iter()
+        .position(|c| !c.2 && exact(c))
+        .or_else(|| candidates.iter().position(|c| c.2 && exact(c)))
+        .or_else(|| candidates.iter().position(|c| !c.2 && partial(c)))
+}
+
 async fn parse_form_answers(
     s: Vec<serenity::model::prelude::EmbedField>,
 ) -> Result<FormAnswers, Box<dyn std::error::Error>> {
+    if s.len() < 5 {
+        return Err(format!("expected at least 5 answers, got {}", s.len()).into());
+    }
     let discord_tag = &s[0].value;
     let status = match s[1].value.as_str() {
         "Formally diagnosed with ASD (Autism spectrum Disorder)" => DiagnosisStatus::Formal,
         "Questioning ASD" => DiagnosisStatus::Questioning,
         "Self Diagnosed" => DiagnosisStatus::SelfDiagnose,
         "Family Member or Friend of an Autistic Individual." => DiagnosisStatus::FriendOrFamily,
-        _ => panic!("Sir, what the fuck"),
+        other => return Err(format!("unknown diagnosis status {:?}", other).into()),
     };
     let gender = match s[2].value.as_str() {
         "Male" => Gender::Male,
         "Female" => Gender::Female,
         "Other (Non-Binary, Transgender, ETC...)" => Gender::Divers,
-        _ => panic!("sir please"),
+        other => return Err(format!("unknown gender {:?}", other).into()),
     };
     let is_over_18 = match s[3].value.as_str() {
         "Yes" => true,
@@ -592,10 +899,23 @@ async fn parse_form_answers(
         _ => false,
     };
 
-    let is_female = match gender {
-        Gender::Female => true,
-        _ => false,
-    };
+    let is_female = matches!(gender, Gender::Female);
+
+    let age = s
+        .iter()
+        .skip(5)
+        .find(|f| crate::validation::is_age_question(&f.name))
+        .map(|f| f.value.trim().to_string())
+        .filter(|a| !a.is_empty());
+
+    // anything else after the fixed questions is free text
+    let free_text = s
+        .iter()
+        .skip(5)
+        .filter(|f| !f.value.trim().is_empty() && !crate::validation::is_age_question(&f.name))
+        .map(|f| format!("{}: {}", f.name, f.value))
+        .collect::<Vec<_>>()
+        .join("\n");
 
     Ok(FormAnswers {
         discord_tag: discord_tag.to_string(),
@@ -604,5 +924,37 @@ async fn parse_form_answers(
         is_18_plus: is_over_18,
         is_30_plus: is_over_30,
         is_female,
+        age,
+        free_text: if free_text.is_empty() { None } else { Some(free_text) },
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::match_member;
+
+    fn candidate(name: &str, discriminator: &str, verified: bool) -> (String, String, bool) {
+        (name.to_string(), format!("{}#{}", name, discriminator), verified)
+    }
+
+    #[test]
+    fn exact_match_wins_over_partial() {
+        let candidates = vec![candidate("alex", "0001", false), candidate("alexander", "1234", true)];
+        assert_eq!(match_member("alexander#1234", &candidates), Some(1));
+    }
+
+    #[test]
+    fn unverified_wins_among_exact_matches() {
+        let candidates = vec![candidate("bob", "1234", true), candidate("bob", "1234", false)];
+        assert_eq!(match_member("bob#1234", &candidates), Some(1));
+    }
+
+    #[test]
+    fn partial_match_only_for_unverified() {
+        let candidates = vec![candidate("bob", "0001", true)];
+        assert_eq!(match_member("bobby#1234", &candidates), None);
+
+        let candidates = vec![candidate("bob", "0001", false)];
+        assert_eq!(match_member("bob #0001 ", &candidates), Some(0));
+    }
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+use crate::export::ExportFormat;
+use crate::structs::SubmissionStatus;
+
+#[derive(Parser)]
+#[command(version, about = "Google Forms verification bot for Discord")]
+pub struct Cli {
+    /// Path to the SQLite database
+    #[arg(long, default_value = "bot.db", global = true)]
+    pub database: String,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Connect to Discord and process submissions (default)
+    Run,
+    /// Apply pending database migrations and exit
+    Migrate,
+    /// Export submissions and decisions without connecting to Discord
+    Export(ExportArgs),
+    /// Import submissions from a file created by `export`
+    Import(ImportArgs),
+    /// Delete submissions older than a given date
+    Purge(PurgeArgs),
+    /// Show all submissions of a user
+    Show {
+        user_id: u64,
+    },
+    /// Change the status of a submission
+    SetStatus {
+        message_id: u64,
+        status: SubmissionStatus,
+    },
+}
+
+#[derive(Args)]
+pub struct ExportArgs {
+    #[arg(long, value_enum, default_value = "csv")]
+    pub format: ExportFormat,
+
+    /// Only include submissions made on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub since: Option<String>,
+
+    /// Only include submissions made on or before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub until: Option<String>,
+
+    /// Only include submissions with this status (pending, approved, kicked, banned, superseded, rejected, revoked, approved_manually)
+    #[arg(long)]
+    pub status: Option<SubmissionStatus>,
+
+    /// Only include submissions decided by this moderator (user ID)
+    #[arg(long)]
+    pub moderator: Option<u64>,
+
+    /// Write to this file instead of stdout
+    #[arg(long, short)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// File to import
+    pub input: PathBuf,
+
+    /// File format, guessed from the file extension if omitted
+    #[arg(long, value_enum)]
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Args)]
+pub struct PurgeArgs {
+    /// Delete submissions made before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub before: String,
+
+    /// Only delete submissions with this status
+    #[arg(long)]
+    pub status: Option<SubmissionStatus>,
+
+    /// Only print how many submissions would be deleted
+    #[arg(long)]
+    pub dry_run: bool,
+}
@@ -0,0 +1,196 @@
+use serenity::{
+    client::Context,
+    model::interactions::{
+        application_command::{
+            ApplicationCommand, ApplicationCommandInteraction, ApplicationCommandOptionType,
+        },
+        InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+    },
+    utils::Color,
+};
+
+use crate::bot::Bot;
+
+pub async fn register(ctx: &Context) -> serenity::Result<Vec<ApplicationCommand>> {
+    ApplicationCommand::set_global_application_commands(&ctx.http, |commands| {
+        commands.create_application_command(|c| {
+            c.name("forget-me")
+                .description("Delete the answers you submitted through the verification form")
+        });
+        commands.create_application_command(|c| {
+            c.name("reverify")
+                .description("Update your verification answers, for example when your diagnosis changed")
+        });
+        commands.create_application_command(|c| {
+            c.name("revoke")
+                .description("Take back the roles a member got through verification")
+                .create_option(|o| {
+                    o.name("user")
+                        .description("The member to revoke")
+                        .kind(ApplicationCommandOptionType::User)
+                        .required(true)
+                })
+                .create_option(|o| {
+                    o.name("reason")
+                        .description("Why the verification is revoked")
+                        .kind(ApplicationCommandOptionType::String)
+                })
+                .create_option(|o| {
+                    o.name("action")
+                        .description("Also remove the member from the server")
+                        .kind(ApplicationCommandOptionType::String)
+                        .add_string_choice("Kick", "kick")
+                        .add_string_choice("Ban", "ban")
+                })
+        });
+        commands.create_application_command(|c| {
+            c.name("lookup")
+                .description("Show the submissions of a member and their review threads")
+                .create_option(|o| {
+                    o.name("user")
+                        .description("The member to look up")
+                        .kind(ApplicationCommandOptionType::User)
+                        .required(true)
+                })
+        });
+        commands.create_application_command(|c| {
+            c.name("note")
+                .description("Add a private moderator note to a member")
+                .create_option(|o| {
+                    o.name("user")
+                        .description("The member the note is about")
+                        .kind(ApplicationCommandOptionType::User)
+                        .required(true)
+                })
+                .create_option(|o| {
+                    o.name("text")
+                        .description("The note")
+                        .kind(ApplicationCommandOptionType::String)
+                        .required(true);
+                    // this serenity version has no builder method for it yet
+                    o.0.insert("max_length", serde_json::json!(crate::notes::MAX_NOTE_LENGTH));
+                    o
+                })
+        });
+        commands.create_application_command(|c| {
+            c.name("stats")
+                .description("Show verification statistics")
+                .create_option(|o| {
+                    o.name("period")
+                        .description("Time span to look at (default: last week)")
+                        .kind(ApplicationCommandOptionType::String)
+                        .add_string_choice("Last day", "day")
+                        .add_string_choice("Last week", "week")
+                        .add_string_choice("Last 30 days", "month")
+                        .add_string_choice("All time", "all")
+                })
+        });
+        commands.create_application_command(|c| {
+            c.name("sla")
+                .description("Show or change when moderators are reminded of pending submissions")
+                .create_option(|o| {
+                    o.name("alert_hours")
+                        .description("Remind after this many hours, 0 turns reminders off")
+                        .kind(ApplicationCommandOptionType::Integer)
+                        .min_int_value(0)
+                })
+                .create_option(|o| {
+                    o.name("alert_role")
+                        .description("Role to ping with the reminder")
+                        .kind(ApplicationCommandOptionType::Role)
+                })
+                .create_option(|o| {
+                    o.name("escalate_hours")
+                        .description("Escalate after this many hours, 0 turns escalation off")
+                        .kind(ApplicationCommandOptionType::Integer)
+                        .min_int_value(0)
+                })
+                .create_option(|o| {
+                    o.name("escalate_role")
+                        .description("Role to ping when escalating")
+                        .kind(ApplicationCommandOptionType::Role)
+                })
+        });
+        commands.create_application_command(|c| {
+            c.name("export")
+                .description("Export submissions and decisions as a file")
+                .create_option(|o| {
+                    o.name("format")
+                        .description("File format (default: CSV)")
+                        .kind(ApplicationCommandOptionType::String)
+                        .add_string_choice("CSV", "csv")
+                        .add_string_choice("JSON", "json")
+                })
+                .create_option(|o| {
+                    o.name("since")
+                        .description("Only submissions made on or after this date (YYYY-MM-DD)")
+                        .kind(ApplicationCommandOptionType::String)
+                })
+                .create_option(|o| {
+                    o.name("until")
+                        .description("Only submissions made on or before this date (YYYY-MM-DD)")
+                        .kind(ApplicationCommandOptionType::String)
+                })
+                .create_option(|o| {
+                    o.name("status")
+                        .description("Only submissions with this status")
+                        .kind(ApplicationCommandOptionType::String)
+                        .add_string_choice("Pending", "pending")
+                        .add_string_choice("Approved", "approved")
+                        .add_string_choice("Approved manually", "approved_manually")
+                        .add_string_choice("Kicked", "kicked")
+                        .add_string_choice("Banned", "banned")
+                        .add_string_choice("Superseded", "superseded")
+                        .add_string_choice("Rejected", "rejected")
+                        .add_string_choice("Revoked", "revoked")
+                })
+                .create_option(|o| {
+                    o.name("moderator")
+                        .description("Only submissions decided by this moderator")
+                        .kind(ApplicationCommandOptionType::User)
+                })
+        })
+    })
+    .await
+}
+
+pub async fn dispatch(bot: &Bot, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+    match cmd.data.name.as_str() {
+        "forget-me" => bot.forget_me_command(ctx, cmd).await,
+        "export" => bot.export_command(ctx, cmd).await,
+        "reverify" => bot.reverify_command(ctx, cmd).await,
+        "revoke" => bot.revoke_command(ctx, cmd).await,
+        "lookup" => bot.lookup_command(ctx, cmd).await,
+        "note" => bot.note_command(ctx, cmd).await,
+        "stats" => bot.stats_command(ctx, cmd).await,
+        "sla" => bot.sla_command(ctx, cmd).await,
+        other => tracing::warn!("Received unknown command {}", other),
+    }
+}
+
+/// Moderator commands are restricted to members who may kick, since that is what
+/// the review buttons do as well.
+pub fn is_moderator(cmd: &ApplicationCommandInteraction) -> bool {
+    cmd.member
+        .as_ref()
+        .and_then(|m| m.permissions)
+        .map(|p| p.kick_members())
+        .unwrap_or(false)
+}
+
+pub async fn respond_error(ctx: &Context, cmd: &ApplicationCommandInteraction, message: &str) {
+    let _ = cmd
+        .create_interaction_response(ctx, |f| {
+            f.kind(InteractionResponseType::ChannelMessageWithSource);
+            f.interaction_response_data(|f| {
+                f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                f.embed(|e| {
+                    e.title("Error");
+                    e.description(message);
+                    e.color(Color::DARK_RED);
+                    e
+                })
+            })
+        })
+        .await;
+}
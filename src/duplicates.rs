@@ -0,0 +1,137 @@
+use serenity::{
+    client::Context,
+    model::{channel::Message, id::MessageId, interactions::message_component::ButtonStyle},
+    utils::Color,
+};
+
+use crate::bot::{Bot, FormAnswersDB};
+
+fn yes_no(value: bool) -> &'static str {
+    if value {
+        "Yes"
+    } else {
+        "No"
+    }
+}
+
+/// Lists the answers that differ between two submissions of the same member.
+pub fn submission_diff(old: &FormAnswersDB, new: &FormAnswersDB) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if old.gender != new.gender {
+        changes.push(format!("Gender: {} → {}", old.gender, new.gender));
+    }
+    if old.age != new.age {
+        changes.push(format!(
+            "Age: {} → {}",
+            old.age.as_deref().unwrap_or("-"),
+            new.age.as_deref().unwrap_or("-")
+        ));
+    }
+    if old.is_18_plus != new.is_18_plus {
+        changes.push(format!("Over 18: {} → {}", yes_no(old.is_18_plus), yes_no(new.is_18_plus)));
+    }
+    if old.is_30_plus != new.is_30_plus {
+        changes.push(format!("Over 30: {} → {}", yes_no(old.is_30_plus), yes_no(new.is_30_plus)));
+    }
+    if old.diagnosis_status != new.diagnosis_status {
+        changes.push(format!(
+            "Diagnosis: {} → {}",
+            old.diagnosis_status.as_deref().unwrap_or("-"),
+            new.diagnosis_status.as_deref().unwrap_or("-")
+        ));
+    }
+
+    changes
+}
+
+impl Bot {
+    /// Submissions of this member that are still waiting for review.
+    pub async fn active_submissions(&self, guild_id: Option<i64>, user_id: i64) -> Vec<FormAnswersDB> {
+        crate::metrics::db(
+            "active_submissions",
+            sqlx::query_as!(
+                FormAnswersDB,
+                "SELECT * FROM formanswers WHERE (guild_id = ?1 OR guild_id IS NULL) AND user_id = ?2 AND status = 'pending'",
+                guild_id,
+                user_id
+            )
+            .fetch_all(&self.database),
+        )
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Points the review messages of replaced submissions to the new one and shows what changed.
+    pub async fn mark_superseded(&self, ctx: &Context, previous: &[FormAnswersDB], new_msg: &Message) {
+        let new_mid = new_msg.id.0 as i64;
+        let new = match sqlx::query_as!(FormAnswersDB, "SELECT * FROM formanswers WHERE message_id = ?", new_mid)
+            .fetch_one(&self.database)
+            .await
+        {
+            Ok(n) => n,
+            Err(why) => {
+                tracing::error!("Could not load submission {}: {:?}", new_mid, why);
+                return;
+            }
+        };
+        let link = new_msg.link();
+        let mut embed = serenity::builder::CreateEmbed::from(new_msg.embeds[0].clone());
+
+        for old in previous.iter() {
+            let changes = submission_diff(old, &new);
+            let diff = if changes.is_empty() {
+                "No answers changed".to_string()
+            } else {
+                changes.join("\n")
+            };
+
+            let _ = self
+                .responses_channel
+                .edit_message(&ctx.http, MessageId(old.message_id as u64), |m| {
+                    m.embed(|e| {
+                        e.title("Form Submission (superseded)");
+                        e.description(format!(
+                            "<@{}> submitted the form again, review the [new submission]({}) instead.",
+                            old.user_id, link
+                        ));
+                        e.field("Changes", crate::embeds::truncate(&diff, crate::embeds::MAX_FIELD_VALUE), false);
+                        e.color(Color::DARK_GREY);
+                        e.footer(|f| {
+                            f.text(format!("Gotten UserId {}", old.user_id));
+                            f
+                        });
+                        e
+                    });
+                    m.components(|c| {
+                        c.create_action_row(|a| {
+                            a.create_button(|b| {
+                                b.custom_id("superseded");
+                                b.disabled(true);
+                                b.style(ButtonStyle::Secondary);
+                                b.label("Superseded")
+                            })
+                        })
+                    })
+                })
+                .await;
+
+            self.close_review_thread(ctx, old.message_id, &format!("Superseded by a [new submission]({})", link))
+                .await;
+
+            let old_link = format!(
+                "https://discord.com/channels/{}/{}/{}",
+                new_msg.guild_id.map(|g| g.0.to_string()).unwrap_or_else(|| "@me".to_string()),
+                self.responses_channel.0,
+                old.message_id
+            );
+            embed.field("Replaces previous submission", format!("[Jump]({})\n{}", old_link, diff), false);
+        }
+
+        crate::embeds::fit(&mut embed);
+        let _ = new_msg
+            .channel_id
+            .edit_message(&ctx.http, new_msg.id, |m| m.set_embed(embed))
+            .await;
+    }
+}
@@ -0,0 +1,138 @@
+use serde_json::{json, Value};
+use serenity::builder::CreateEmbed;
+use serenity::model::id::RoleId;
+
+/// Discord rejects embeds that go past any of these.
+pub const MAX_FIELDS: usize = 25;
+pub const MAX_FIELD_NAME: usize = 256;
+pub const MAX_FIELD_VALUE: usize = 1024;
+pub const MAX_TOTAL: usize = 6000;
+/// Long values are never cut shorter than this to make the whole embed fit.
+const MIN_CLIPPED_VALUE: usize = 100;
+
+/// Cuts `text` down to `max` characters, ending in "…" if anything was cut.
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut out: String = text.chars().take(max.saturating_sub(1)).collect();
+    out.push('…');
+    out
+}
+
+/// Role mentions for an embed, "None" if there are no roles.
+pub fn mention_roles(roles: &[RoleId]) -> String {
+    if roles.is_empty() {
+        return "None".to_string();
+    }
+    roles.iter().map(|r| format!("<@&{}>", r.0)).collect::<Vec<_>>().join(" ")
+}
+
+fn text_len(value: Option<&Value>) -> usize {
+    value.and_then(Value::as_str).map_or(0, |s| s.chars().count())
+}
+
+fn clip(field: &mut Value, key: &str, max: usize) {
+    if let Some(text) = field.get(key).and_then(Value::as_str) {
+        if text.chars().count() > max {
+            field[key] = Value::from(truncate(text, max));
+        }
+    }
+}
+
+/// Shrinks an embed until Discord accepts it. Fields past the limit are replaced by a list of their names
+/// and the longest values are shortened first.
+pub fn fit(embed: &mut CreateEmbed) {
+    let fixed = text_len(embed.0.get("title"))
+        + text_len(embed.0.get("description"))
+        + text_len(embed.0.get("footer").and_then(|f| f.get("text")))
+        + text_len(embed.0.get("author").and_then(|a| a.get("name")));
+    let fields = match embed.0.get_mut("fields").and_then(Value::as_array_mut) {
+        Some(f) => f,
+        None => return,
+    };
+
+    if fields.len() > MAX_FIELDS {
+        let dropped: Vec<Value> = fields.drain(MAX_FIELDS - 1..).collect();
+        let names: Vec<&str> = dropped.iter().filter_map(|f| f.get("name").and_then(Value::as_str)).collect();
+        fields.push(json!({ "name": "Not shown", "value": names.join(", "), "inline": false }));
+    }
+    for field in fields.iter_mut() {
+        clip(field, "name", MAX_FIELD_NAME);
+        clip(field, "value", MAX_FIELD_VALUE);
+    }
+
+    loop {
+        let total = fixed
+            + fields
+                .iter()
+                .map(|f| text_len(f.get("name")) + text_len(f.get("value")))
+                .sum::<usize>();
+        if total <= MAX_TOTAL {
+            break;
+        }
+        let longest = fields.iter_mut().max_by_key(|f| text_len(f.get("value")));
+        let field = match longest {
+            Some(f) if text_len(f.get("value")) > MIN_CLIPPED_VALUE => f,
+            _ => break,
+        };
+        let length = text_len(field.get("value"));
+        clip(field, "value", length.saturating_sub(total - MAX_TOTAL).max(MIN_CLIPPED_VALUE));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field_values(embed: &CreateEmbed) -> Vec<String> {
+        embed.0["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|f| f["value"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn truncates_with_ellipsis() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("abcdef", 4), "abc…");
+    }
+
+    #[test]
+    fn clips_long_values() {
+        let mut embed = CreateEmbed::default();
+        embed.field("Answer", "x".repeat(2000), false);
+        fit(&mut embed);
+        assert_eq!(field_values(&embed)[0].chars().count(), MAX_FIELD_VALUE);
+    }
+
+    #[test]
+    fn lists_fields_past_the_limit() {
+        let mut embed = CreateEmbed::default();
+        for i in 0..30 {
+            embed.field(format!("Field {}", i), "value", false);
+        }
+        fit(&mut embed);
+        let fields = embed.0["fields"].as_array().unwrap();
+        assert_eq!(fields.len(), MAX_FIELDS);
+        assert_eq!(fields[MAX_FIELDS - 1]["name"], "Not shown");
+        assert!(fields[MAX_FIELDS - 1]["value"].as_str().unwrap().starts_with("Field 24, "));
+    }
+
+    #[test]
+    fn shortens_longest_values_to_fit_the_total() {
+        let mut embed = CreateEmbed::default();
+        embed.title("Submission");
+        for _ in 0..8 {
+            embed.field("Answer", "x".repeat(1000), false);
+        }
+        embed.field("Short", "kept", false);
+        fit(&mut embed);
+        let values = field_values(&embed);
+        let total = "Submission".len() + 8 * "Answer".len() + "Short".len() + values.iter().map(|v| v.chars().count()).sum::<usize>();
+        assert!(total <= MAX_TOTAL);
+        assert_eq!(values[8], "kept");
+    }
+}
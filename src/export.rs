@@ -0,0 +1,287 @@
+use std::io::Write;
+
+use serde::{Deserialize, Serialize};
+use serenity::{
+    client::Context,
+    model::{
+        channel::AttachmentType,
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction,
+                ApplicationCommandInteractionDataOptionValue as OptionValue,
+            },
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        Timestamp,
+    },
+};
+use sqlx::SqlitePool;
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::cli::{ExportArgs, ImportArgs};
+use crate::structs::SubmissionStatus;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ExportFilter {
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub status: Option<SubmissionStatus>,
+    pub moderator: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExportRow {
+    message_id: String,
+    guild_id: Option<String>,
+    user_id: String,
+    status: String,
+    moderator_id: Option<String>,
+    submitted_at: Option<String>,
+    decided_at: Option<String>,
+    redacted_at: Option<String>,
+    age: Option<String>,
+    gender: String,
+    is_female: bool,
+    is_18_plus: bool,
+    is_30_plus: bool,
+    diagnosis_status: Option<String>,
+    free_text: Option<String>,
+}
+
+fn format_timestamp(ts: Option<i64>) -> Option<String> {
+    ts.and_then(|ts| Timestamp::from_unix_timestamp(ts).ok())
+        .map(|ts| ts.to_string())
+}
+
+impl From<FormAnswersDB> for ExportRow {
+    fn from(f: FormAnswersDB) -> Self {
+        ExportRow {
+            message_id: f.message_id.to_string(),
+            guild_id: f.guild_id.map(|id| id.to_string()),
+            user_id: f.user_id.to_string(),
+            status: f.status,
+            moderator_id: f.moderator_id.map(|id| id.to_string()),
+            submitted_at: format_timestamp(f.submitted_at),
+            decided_at: format_timestamp(f.decided_at),
+            redacted_at: format_timestamp(f.redacted_at),
+            age: f.age,
+            gender: f.gender,
+            is_female: f.is_female,
+            is_18_plus: f.is_18_plus,
+            is_30_plus: f.is_30_plus,
+            diagnosis_status: f.diagnosis_status,
+            free_text: f.free_text,
+        }
+    }
+}
+
+fn parse_timestamp(ts: Option<&str>) -> Result<Option<i64>, String> {
+    ts.filter(|ts| !ts.is_empty())
+        .map(|ts| parse_date(ts, false))
+        .transpose()
+}
+
+fn parse_id(id: &str) -> Result<i64, String> {
+    id.parse().map_err(|_| format!("`{}` is not a valid ID", id))
+}
+
+/// Parses `YYYY-MM-DD` or a full RFC 3339 timestamp into unix seconds.
+///
+/// A bare date as the upper bound covers the whole day.
+pub fn parse_date(input: &str, end_of_day: bool) -> Result<i64, String> {
+    if let Ok(ts) = Timestamp::parse(input) {
+        return Ok(ts.unix_timestamp());
+    }
+
+    let ts = Timestamp::parse(&format!("{}T00:00:00Z", input))
+        .map_err(|_| format!("`{}` is not a date (expected YYYY-MM-DD)", input))?;
+
+    if end_of_day {
+        Ok(ts.unix_timestamp() + 24 * 60 * 60 - 1)
+    } else {
+        Ok(ts.unix_timestamp())
+    }
+}
+
+pub async fn export(
+    pool: &SqlitePool,
+    filter: &ExportFilter,
+    format: ExportFormat,
+) -> Result<Vec<u8>, crate::Error> {
+    let status = filter.status.map(|s| s.as_str());
+    let rows = sqlx::query_as!(
+        FormAnswersDB,
+        "SELECT * FROM formanswers
+        WHERE (?1 IS NULL OR submitted_at >= ?1)
+          AND (?2 IS NULL OR submitted_at <= ?2)
+          AND (?3 IS NULL OR status = ?3)
+          AND (?4 IS NULL OR moderator_id = ?4)
+        ORDER BY submitted_at",
+        filter.since,
+        filter.until,
+        status,
+        filter.moderator
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let rows: Vec<ExportRow> = rows.into_iter().map(ExportRow::from).collect();
+
+    match format {
+        ExportFormat::Json => Ok(serde_json::to_vec_pretty(&rows)?),
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in rows.iter() {
+                writer.serialize(row)?;
+            }
+            Ok(writer.into_inner().map_err(|e| e.into_error())?)
+        }
+    }
+}
+
+pub async fn export_cli(pool: &SqlitePool, args: ExportArgs) -> Result<(), crate::Error> {
+    let filter = ExportFilter {
+        since: args.since.as_deref().map(|d| parse_date(d, false)).transpose()?,
+        until: args.until.as_deref().map(|d| parse_date(d, true)).transpose()?,
+        status: args.status,
+        moderator: args.moderator.map(|id| id as i64),
+    };
+
+    let data = export(pool, &filter, args.format).await?;
+
+    match args.output {
+        Some(path) => std::fs::write(path, data)?,
+        None => std::io::stdout().write_all(&data)?,
+    }
+
+    Ok(())
+}
+
+/// Inserts exported rows back into the database, skipping submissions that already exist
+/// or would clash with a submission that is waiting for review.
+///
+/// Returns how many rows were inserted.
+pub async fn import(pool: &SqlitePool, data: &[u8], format: ExportFormat) -> Result<u64, crate::Error> {
+    let rows: Vec<ExportRow> = match format {
+        ExportFormat::Json => serde_json::from_slice(data)?,
+        ExportFormat::Csv => csv::Reader::from_reader(data)
+            .deserialize()
+            .collect::<Result<_, _>>()?,
+    };
+
+    let mut tx = pool.begin().await?;
+    let mut inserted = 0;
+    for row in rows {
+        let message_id = parse_id(&row.message_id)?;
+        let user_id = parse_id(&row.user_id)?;
+        let guild_id = row.guild_id.as_deref().filter(|id| !id.is_empty()).map(parse_id).transpose()?;
+        let moderator_id = row.moderator_id.as_deref().filter(|id| !id.is_empty()).map(parse_id).transpose()?;
+        let submitted_at = parse_timestamp(row.submitted_at.as_deref())?;
+        let decided_at = parse_timestamp(row.decided_at.as_deref())?;
+        let redacted_at = parse_timestamp(row.redacted_at.as_deref())?;
+        let status: SubmissionStatus = row.status.parse()?;
+        let status = status.as_str();
+
+        let result = sqlx::query!(
+            "INSERT OR IGNORE INTO formanswers (message_id, user_id, age, gender, is_female, is_18_plus, is_30_plus, diagnosis_status, status, moderator_id, submitted_at, decided_at, redacted_at, guild_id, free_text)
+            SELECT ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15
+            WHERE NOT EXISTS (SELECT 1 FROM formanswers WHERE message_id = ?1)",
+            message_id, user_id, row.age, row.gender, row.is_female, row.is_18_plus, row.is_30_plus,
+            row.diagnosis_status, status, moderator_id, submitted_at, decided_at, redacted_at, guild_id, row.free_text
+        )
+        .execute(&mut tx)
+        .await?;
+        inserted += result.rows_affected();
+    }
+    tx.commit().await?;
+
+    Ok(inserted)
+}
+
+pub async fn import_cli(pool: &SqlitePool, args: ImportArgs) -> Result<(), crate::Error> {
+    let format = match args.format {
+        Some(format) => format,
+        None => match args.input.extension().and_then(|e| e.to_str()) {
+            Some("json") => ExportFormat::Json,
+            Some("csv") => ExportFormat::Csv,
+            _ => return Err("cannot guess the file format, pass --format".into()),
+        },
+    };
+
+    let data = std::fs::read(&args.input)?;
+    let inserted = import(pool, &data, format).await?;
+    eprintln!("Imported {} submission(s)", inserted);
+
+    Ok(())
+}
+
+impl Bot {
+    pub async fn export_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        if !crate::commands::is_moderator(cmd) {
+            crate::commands::respond_error(ctx, cmd, "Only moderators can export submissions.").await;
+            return;
+        }
+
+        let mut format = ExportFormat::Csv;
+        let mut filter = ExportFilter::default();
+        for option in cmd.data.options.iter() {
+            let parsed = match (option.name.as_str(), option.resolved.as_ref()) {
+                ("format", Some(OptionValue::String(s))) if s == "json" => {
+                    format = ExportFormat::Json;
+                    Ok(())
+                }
+                ("since", Some(OptionValue::String(s))) => parse_date(s, false).map(|ts| filter.since = Some(ts)),
+                ("until", Some(OptionValue::String(s))) => parse_date(s, true).map(|ts| filter.until = Some(ts)),
+                ("status", Some(OptionValue::String(s))) => s.parse().map(|s| filter.status = Some(s)),
+                ("moderator", Some(OptionValue::User(u, _))) => {
+                    filter.moderator = Some(u.id.0 as i64);
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+
+            if let Err(why) = parsed {
+                crate::commands::respond_error(ctx, cmd, &why).await;
+                return;
+            }
+        }
+
+        let data = match export(&self.database, &filter, format).await {
+            Ok(data) => data,
+            Err(why) => {
+                tracing::error!("Export failed: {:?}", why);
+                crate::commands::respond_error(ctx, cmd, "Could not export submissions.").await;
+                return;
+            }
+        };
+
+        let filename = format!("submissions-{}.{}", Timestamp::now().unix_timestamp(), format.extension());
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.add_file(AttachmentType::Bytes {
+                        data: data.into(),
+                        filename,
+                    })
+                })
+            })
+            .await;
+    }
+}
@@ -0,0 +1,343 @@
+use std::time::Duration;
+
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::{
+        guild::{audit_log::MemberAction, Member},
+        id::{GuildId, MessageId, UserId},
+        interactions::message_component::ButtonStyle,
+        prelude::User,
+        Timestamp,
+    },
+    utils::Color,
+};
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::structs::SubmissionStatus;
+
+/// Discord writes the audit log entry shortly after the gateway event.
+const AUDIT_LOG_DELAY: Duration = Duration::from_secs(2);
+/// Audit log entries older than this belong to an earlier action against the same user.
+const MAX_ENTRY_AGE: i64 = 60;
+
+/// Who did something to a member, according to the audit log.
+struct Actor {
+    id: UserId,
+    tag: String,
+    reason: Option<String>,
+}
+
+async fn find_actor(ctx: &Context, guild_id: GuildId, action: MemberAction, target: UserId) -> Option<Actor> {
+    tokio::time::sleep(AUDIT_LOG_DELAY).await;
+
+    let logs = match guild_id.audit_logs(&ctx.http, Some(action as u8), None, None, Some(10)).await {
+        Ok(l) => l,
+        Err(why) => {
+            tracing::error!("Could not read the audit log of {}: {:?}", guild_id, why);
+            return None;
+        }
+    };
+
+    let now = Timestamp::now().unix_timestamp();
+    let entry = logs.entries.iter().find(|e| {
+        e.target_id == Some(target.0) && now - e.id.created_at().unix_timestamp() <= MAX_ENTRY_AGE
+    })?;
+
+    Some(Actor {
+        id: entry.user_id,
+        tag: logs
+            .users
+            .get(&entry.user_id)
+            .map(|u| u.tag())
+            .unwrap_or_else(|| entry.user_id.to_string()),
+        reason: entry.reason.clone(),
+    })
+}
+
+impl Bot {
+    /// The submission an action outside the bot applies to: the newest one, unless it is already settled that way.
+    /// Older submissions are never touched, their decision stands.
+    async fn latest_open_submission(&self, guild_id: GuildId, user_id: i64, status: SubmissionStatus) -> Option<FormAnswersDB> {
+        let gid = guild_id.0 as i64;
+        crate::metrics::db(
+            "latest_open_submission",
+            sqlx::query_as!(
+                FormAnswersDB,
+                "SELECT * FROM formanswers WHERE user_id = ? AND (guild_id = ? OR guild_id IS NULL) AND status != 'superseded'
+                ORDER BY submitted_at DESC, rowid DESC LIMIT 1",
+                user_id,
+                gid
+            )
+            .fetch_optional(&self.database),
+        )
+        .await
+        .ok()
+        .flatten()
+        .filter(|s| s.status != status.as_str())
+    }
+
+    /// Adds a field to a review message and optionally replaces its buttons with a disabled label.
+    async fn annotate_review(
+        &self,
+        ctx: &Context,
+        message_id: i64,
+        name: &str,
+        value: &str,
+        label: Option<(&str, ButtonStyle)>,
+    ) {
+        let message = match self.responses_channel.message(ctx, MessageId(message_id as u64)).await {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+        let mut embed = match message.embeds.first() {
+            Some(e) => CreateEmbed::from(e.clone()),
+            None => return,
+        };
+        embed.field(name, value, false);
+        crate::embeds::fit(&mut embed);
+
+        let _ = self
+            .responses_channel
+            .edit_message(ctx, message.id, |m| {
+                m.set_embed(embed);
+                if let Some((label, style)) = label {
+                    m.components(|c| {
+                        c.create_action_row(|a| {
+                            a.create_button(|b| {
+                                b.label(label);
+                                b.style(style);
+                                b.custom_id("external_action");
+                                b.disabled(true);
+                                b
+                            })
+                        })
+                    });
+                }
+                m
+            })
+            .await;
+    }
+
+    /// Records a kick or ban that a moderator did through Discord instead of the review buttons.
+    #[tracing::instrument(skip_all, fields(status = status.as_str()))]
+    async fn record_external(&self, ctx: &Context, guild_id: GuildId, user: &User, status: SubmissionStatus, action: MemberAction) {
+        let uid = user.id.0 as i64;
+        // most members leave on their own, only look at the audit log if there is a submission to close
+        if status == SubmissionStatus::Kicked && self.latest_open_submission(guild_id, uid, status).await.is_none() {
+            return;
+        }
+
+        let actor = find_actor(ctx, guild_id, action, user.id).await;
+        // decisions made through the bot are recorded already
+        if actor.as_ref().map(|a| a.id) == Some(ctx.cache.current_user_id()) {
+            return;
+        }
+        if status == SubmissionStatus::Kicked && actor.is_none() {
+            // no kick in the audit log, the member left on their own
+            return;
+        }
+
+        let verb = status.as_str();
+        let by = actor.as_ref().map(|a| a.tag.clone()).unwrap_or_else(|| "unknown".to_string());
+        let reason = actor.as_ref().and_then(|a| a.reason.clone()).unwrap_or_else(|| "No reason given".to_string());
+
+        crate::audit::record(
+            &self.database,
+            guild_id.0 as i64,
+            uid,
+            actor.as_ref().map(|a| a.id.0 as i64),
+            verb,
+            Some(&format!("Outside the bot: {}", reason)),
+        )
+        .await;
+
+        let submission = match self.latest_open_submission(guild_id, uid, status).await {
+            Some(s) => s,
+            None => return,
+        };
+
+        let moderator_id = actor.as_ref().map(|a| a.id.0 as i64);
+        let decided_at = Timestamp::now().unix_timestamp();
+        // an earlier decision keeps its moderator and time, the audit log has who kicked or banned
+        if let Err(why) = crate::metrics::db(
+            "record_decision",
+            sqlx::query!(
+                "UPDATE formanswers SET status = ?1,
+                    moderator_id = CASE WHEN status = 'pending' THEN ?2 ELSE moderator_id END,
+                    decided_at = CASE WHEN status = 'pending' THEN ?3 ELSE decided_at END
+                WHERE message_id = ?4",
+                verb,
+                moderator_id,
+                decided_at,
+                submission.message_id
+            )
+            .execute(&self.database),
+        )
+        .await
+        {
+            tracing::error!("Could not record external {} of {}: {:?}", verb, user.tag(), why);
+            return;
+        }
+        crate::metrics::DECISIONS.with_label_values(&[verb]).inc();
+
+        let title = format!("{} externally", if status == SubmissionStatus::Banned { "Banned" } else { "Kicked" });
+        self.annotate_review(
+            ctx,
+            submission.message_id,
+            &title,
+            &format!("By {} on <t:{}:f>\nReason: {}", by, decided_at, reason),
+            Some((&format!("{} by {}", title, by), ButtonStyle::Danger)),
+        )
+        .await;
+        self.close_review_thread(ctx, submission.message_id, &format!("{} by {}\nReason: {}", title, by, reason))
+            .await;
+    }
+
+    pub async fn external_ban(&self, ctx: &Context, guild_id: GuildId, user: &User) {
+        self.record_external(ctx, guild_id, user, SubmissionStatus::Banned, MemberAction::BanAdd)
+            .await;
+    }
+
+    /// Checks whether a member who left was kicked by someone outside the bot.
+    pub async fn external_kick(&self, ctx: &Context, guild_id: GuildId, user: &User) {
+        self.record_external(ctx, guild_id, user, SubmissionStatus::Kicked, MemberAction::Kick)
+            .await;
+    }
+
+    /// Unbans are only noted, the ban stays the recorded decision of the submission.
+    pub async fn external_unban(&self, ctx: &Context, guild_id: GuildId, user: &User) {
+        let actor = find_actor(ctx, guild_id, MemberAction::BanRemove, user.id).await;
+        let uid = user.id.0 as i64;
+        let by = actor.as_ref().map(|a| a.tag.clone()).unwrap_or_else(|| "unknown".to_string());
+        let reason = actor.as_ref().and_then(|a| a.reason.clone()).unwrap_or_else(|| "No reason given".to_string());
+
+        crate::audit::record(
+            &self.database,
+            guild_id.0 as i64,
+            uid,
+            actor.as_ref().map(|a| a.id.0 as i64),
+            "unban",
+            Some(&reason),
+        )
+        .await;
+
+        let gid = guild_id.0 as i64;
+        let banned = sqlx::query!(
+            "SELECT message_id FROM formanswers WHERE user_id = ? AND (guild_id = ? OR guild_id IS NULL) AND status = 'banned'
+            ORDER BY decided_at DESC LIMIT 1",
+            uid,
+            gid
+        )
+        .fetch_optional(&self.database)
+        .await
+        .ok()
+        .flatten();
+
+        if let Some(banned) = banned {
+            let now = Timestamp::now().unix_timestamp();
+            self.annotate_review(
+                ctx,
+                banned.message_id,
+                "Unbanned",
+                &format!("By {} on <t:{}:f>\nReason: {}", by, now, reason),
+                None,
+            )
+            .await;
+        }
+
+        let _ = self
+            .responses_channel
+            .send_message(ctx, |f| {
+                f.embed(|e| {
+                    e.title("Member unbanned");
+                    e.description(format!("{} (<@{}>) was unbanned by {}.", user.tag(), uid, by));
+                    e.field("Reason", &reason, false);
+                    e.color(Color::DARK_GREEN);
+                    e
+                })
+            })
+            .await;
+    }
+
+    /// Closes the pending submission of a member who got the member role from a moderator by hand.
+    pub async fn manual_verification(&self, ctx: &Context, old: Option<&Member>, new: &Member) {
+        let role = self.roles.default_member_role;
+        if !new.roles.contains(&role) || old.map(|o| o.roles.contains(&role)).unwrap_or(false) {
+            return;
+        }
+
+        let uid = new.user.id.0 as i64;
+        let gid = new.guild_id.0 as i64;
+        if self.pending_submission(gid, uid).await.is_none() {
+            return;
+        }
+
+        let actor = find_actor(ctx, new.guild_id, MemberAction::RoleUpdate, new.user.id).await;
+        // the bot hands out the role itself when a submission is approved through the buttons
+        if actor.as_ref().map(|a| a.id) == Some(ctx.cache.current_user_id()) {
+            return;
+        }
+        // look again, the approve button may have been used in the meantime
+        let submission = match self.pending_submission(gid, uid).await {
+            Some(s) => s,
+            None => return,
+        };
+
+        let status = SubmissionStatus::ApprovedManually.as_str();
+        let moderator_id = actor.as_ref().map(|a| a.id.0 as i64);
+        let decided_at = Timestamp::now().unix_timestamp();
+        let result = crate::metrics::db(
+            "record_decision",
+            sqlx::query!(
+                "UPDATE formanswers SET status = ?, moderator_id = ?, decided_at = ? WHERE message_id = ? AND status = 'pending'",
+                status,
+                moderator_id,
+                decided_at,
+                submission.message_id
+            )
+            .execute(&self.database),
+        )
+        .await;
+        match result {
+            // decided through the buttons while we were looking at the audit log
+            Ok(r) if r.rows_affected() == 0 => return,
+            Ok(_) => crate::metrics::DECISIONS.with_label_values(&[status]).inc(),
+            Err(why) => {
+                tracing::error!("Could not close submission of {}: {:?}", new.user.tag(), why);
+                return;
+            }
+        }
+        self.track_age(new.guild_id, &submission).await;
+
+        let by = actor.as_ref().map(|a| a.tag.clone()).unwrap_or_else(|| "unknown".to_string());
+        crate::audit::record(&self.database, gid, uid, moderator_id, status, Some("Member role given by hand")).await;
+        self.annotate_review(
+            ctx,
+            submission.message_id,
+            "Approved manually",
+            &format!("{} gave the member role by hand on <t:{}:f>", by, decided_at),
+            Some((&format!("Approved manually by {}", by), ButtonStyle::Success)),
+        )
+        .await;
+        self.close_review_thread(ctx, submission.message_id, &format!("Approved manually by {}", by))
+            .await;
+    }
+
+    async fn pending_submission(&self, guild_id: i64, user_id: i64) -> Option<FormAnswersDB> {
+        crate::metrics::db(
+            "pending_submission",
+            sqlx::query_as!(
+                FormAnswersDB,
+                "SELECT * FROM formanswers WHERE user_id = ?1 AND (guild_id = ?2 OR guild_id IS NULL) AND status = 'pending'
+                ORDER BY submitted_at DESC, rowid DESC LIMIT 1",
+                user_id,
+                guild_id
+            )
+            .fetch_optional(&self.database),
+        )
+        .await
+        .ok()
+        .flatten()
+    }
+}
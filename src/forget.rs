@@ -0,0 +1,343 @@
+use serenity::{
+    client::Context,
+    model::{
+        id::{ChannelId, MessageId},
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        Timestamp,
+    },
+    utils::Color,
+};
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::structs::SubmissionStatus;
+
+/// What `/forget-me` removed.
+#[derive(Default)]
+struct Erased {
+    redacted_at: i64,
+    withdrawn: usize,
+    redacted: usize,
+    fingerprints: u64,
+    notes: u64,
+    age_tracking: u64,
+}
+
+impl Erased {
+    fn is_empty(&self) -> bool {
+        self.withdrawn + self.redacted == 0 && self.fingerprints + self.notes + self.age_tracking == 0
+    }
+
+    fn summary(&self) -> String {
+        let mut summary = String::new();
+        if self.withdrawn > 0 {
+            summary.push_str(&format!(
+                "Withdrew and deleted {} pending submission(s), including the copy our moderators review.\n",
+                self.withdrawn
+            ));
+        }
+        if self.redacted > 0 {
+            summary.push_str(&format!(
+                "Removed your answers (gender, age, age brackets, diagnosis status, free-text answers) from {} decided submission(s). \
+                We kept your user ID, the decision and its date.\n",
+                self.redacted
+            ));
+        }
+        if self.fingerprints > 0 {
+            summary.push_str(&format!(
+                "Deleted {} record(s) of your name, tag and free-text answers kept from rejected submissions.\n",
+                self.fingerprints
+            ));
+        }
+        if self.notes > 0 {
+            summary.push_str(&format!("Deleted {} moderator note(s) about you.\n", self.notes));
+        }
+        if self.age_tracking > 0 {
+            summary.push_str("Deleted the age we kept to move you into the right age group over time.\n");
+        }
+        if summary.is_empty() {
+            summary.push_str("We don't hold any form answers for you.");
+        }
+        summary
+    }
+}
+
+impl Bot {
+    async fn erasable_submissions(&self, user_id: i64) -> Result<Vec<FormAnswersDB>, sqlx::Error> {
+        sqlx::query_as!(
+            FormAnswersDB,
+            "SELECT * FROM formanswers WHERE user_id = ? AND redacted_at IS NULL",
+            user_id
+        )
+        .fetch_all(&self.database)
+        .await
+    }
+
+    pub async fn forget_me_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        let uid = cmd.user.id.0 as i64;
+        let submissions = match self.erasable_submissions(uid).await {
+            Ok(s) => s,
+            Err(why) => {
+                tracing::error!("Could not look up submissions of {}: {:?}", uid, why);
+                Vec::new()
+            }
+        };
+        let related = self.related_rows(uid).await.unwrap_or_default();
+
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+
+                    if submissions.is_empty() && related == 0 {
+                        return f.embed(|e| {
+                            e.title("Nothing to delete");
+                            e.description("We don't hold any form answers for you.");
+                            e.color(Color::DARK_GREEN);
+                            e
+                        });
+                    }
+
+                    f.embed(|e| {
+                        e.title("Delete your form data?");
+                        e.description(format!(
+                            "This removes the answers of {} form submission(s): gender, age, age brackets, diagnosis status and any free-text answers. \
+                            Moderator notes about you, the age we keep to update your age group and the records kept from rejected submissions are deleted as well.\n\n\
+                            Submissions that are still waiting for review are withdrawn completely. \
+                            For submissions that were already decided we only keep your user ID, the decision and its date, \
+                            so moderators know a decision was made.",
+                            submissions.len()
+                        ));
+                        e.color(Color::ORANGE);
+                        e
+                    });
+                    f.components(|c| {
+                        c.create_action_row(|a| {
+                            a.create_button(|b| {
+                                b.label("Delete my data");
+                                b.style(ButtonStyle::Danger);
+                                b.custom_id("forget_me_confirm");
+                                b
+                            });
+                            a.create_button(|b| {
+                                b.label("Cancel");
+                                b.style(ButtonStyle::Secondary);
+                                b.custom_id("forget_me_cancel");
+                                b
+                            })
+                        })
+                    })
+                })
+            })
+            .await;
+    }
+
+    pub async fn forget_me_button(&self, ctx: &Context, msgc: &MessageComponentInteraction) {
+        if msgc.data.custom_id != "forget_me_confirm" {
+            let _ = msgc
+                .create_interaction_response(ctx, |f| {
+                    f.kind(InteractionResponseType::UpdateMessage);
+                    f.interaction_response_data(|f| {
+                        f.embed(|e| {
+                            e.title("Cancelled");
+                            e.description("Nothing was deleted.");
+                            e.color(Color::DARK_GREY);
+                            e
+                        });
+                        f.components(|c| c)
+                    })
+                })
+                .await;
+            return;
+        }
+
+        let uid = msgc.user.id.0 as i64;
+        let submissions = match self.erasable_submissions(uid).await {
+            Ok(s) => s,
+            Err(why) => {
+                tracing::error!("Could not look up submissions of {}: {:?}", uid, why);
+                let _ = msgc
+                    .create_interaction_response(ctx, |f| {
+                        f.kind(InteractionResponseType::UpdateMessage);
+                        f.interaction_response_data(|f| {
+                            f.embed(|e| {
+                                e.title("Error");
+                                e.description("Could not reach the database, nothing was deleted. Please try again later.");
+                                e.color(Color::DARK_RED);
+                                e
+                            });
+                            f.components(|c| c)
+                        })
+                    })
+                    .await;
+                return;
+            }
+        };
+
+        let erased = match self.erase(uid, &submissions).await {
+            Ok(e) => e,
+            Err(why) => {
+                tracing::error!("Could not erase data of {}: {:?}", uid, why);
+                let _ = msgc
+                    .create_interaction_response(ctx, |f| {
+                        f.kind(InteractionResponseType::UpdateMessage);
+                        f.interaction_response_data(|f| {
+                            f.embed(|e| {
+                                e.title("Error");
+                                e.description("Your data could not be deleted, nothing was changed. Please try again later or contact a moderator.");
+                                e.color(Color::DARK_RED);
+                                e
+                            });
+                            f.components(|c| c)
+                        })
+                    })
+                    .await;
+                return;
+            }
+        };
+
+        // the database is settled, now clean up what moderators see
+        for submission in submissions.iter() {
+            if submission.status == SubmissionStatus::Pending.as_str() {
+                self.withdraw_review(ctx, submission).await;
+            } else {
+                self.redact_review(ctx, submission, erased.redacted_at).await;
+            }
+        }
+
+        let summary = erased.summary();
+        let _ = msgc
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::UpdateMessage);
+                f.interaction_response_data(|f| {
+                    f.embed(|e| {
+                        e.title("Your data was removed");
+                        e.description(&summary);
+                        e.color(Color::DARK_GREEN);
+                        e
+                    });
+                    f.components(|c| c)
+                })
+            })
+            .await;
+
+        // keep a receipt in the user's DMs when the command was used on the server
+        if msgc.guild_id.is_some() && !erased.is_empty() {
+            let _ = msgc
+                .user
+                .direct_message(ctx, |m| {
+                    m.embed(|e| {
+                        e.title("Your data was removed");
+                        e.description(&summary);
+                        e.color(Color::DARK_GREEN);
+                        e
+                    })
+                })
+                .await;
+        }
+    }
+
+    /// Erases everything we keep about the user in one transaction, so nothing is left half deleted.
+    async fn erase(&self, user_id: i64, submissions: &[FormAnswersDB]) -> Result<Erased, sqlx::Error> {
+        let mut erased = Erased {
+            redacted_at: Timestamp::now().unix_timestamp(),
+            ..Erased::default()
+        };
+
+        let mut tx = self.database.begin().await?;
+        for submission in submissions.iter() {
+            if submission.status == SubmissionStatus::Pending.as_str() {
+                sqlx::query!("DELETE FROM formanswers WHERE message_id = ?", submission.message_id)
+                    .execute(&mut tx)
+                    .await?;
+                erased.withdrawn += 1;
+            } else {
+                sqlx::query!(
+                    "UPDATE formanswers SET age = NULL, gender = 'redacted', is_female = FALSE, is_18_plus = FALSE, is_30_plus = FALSE, diagnosis_status = NULL, free_text = NULL, validation_warnings = NULL, redacted_at = ? WHERE message_id = ?",
+                    erased.redacted_at,
+                    submission.message_id
+                )
+                .execute(&mut tx)
+                .await?;
+                erased.redacted += 1;
+            }
+        }
+
+        erased.fingerprints = sqlx::query!("DELETE FROM rejected_fingerprints WHERE user_id = ?", user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        erased.notes = sqlx::query!("DELETE FROM moderator_notes WHERE user_id = ?", user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        erased.age_tracking = sqlx::query!("DELETE FROM age_tracking WHERE user_id = ?", user_id)
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+
+        Ok(erased)
+    }
+
+    /// Other data about the user that `/forget-me` deletes along with the answers.
+    async fn related_rows(&self, user_id: i64) -> Result<i64, sqlx::Error> {
+        let row = sqlx::query!(
+            r#"SELECT (SELECT COUNT(*) FROM rejected_fingerprints WHERE user_id = ?1)
+                + (SELECT COUNT(*) FROM moderator_notes WHERE user_id = ?1)
+                + (SELECT COUNT(*) FROM age_tracking WHERE user_id = ?1) AS "count!: i64""#,
+            user_id
+        )
+        .fetch_one(&self.database)
+        .await?;
+        Ok(row.count)
+    }
+
+    async fn withdraw_review(&self, ctx: &Context, submission: &FormAnswersDB) {
+        // the review message may have been deleted by hand already
+        let _ = self
+            .responses_channel
+            .delete_message(&ctx.http, MessageId(submission.message_id as u64))
+            .await;
+        if let Some(thread_id) = submission.thread_id {
+            let _ = ChannelId(thread_id as u64).delete(&ctx.http).await;
+        }
+    }
+
+    async fn redact_review(&self, ctx: &Context, submission: &FormAnswersDB, redacted_at: i64) {
+        let decided = match submission.decided_at {
+            Some(ts) => format!("<t:{}:f>", ts),
+            None => "an unknown date".to_string(),
+        };
+        let moderator = match submission.moderator_id {
+            Some(id) => format!("<@{}>", id),
+            None => "unknown".to_string(),
+        };
+
+        let _ = self
+            .responses_channel
+            .edit_message(&ctx.http, MessageId(submission.message_id as u64), |m| {
+                m.embed(|e| {
+                    e.title("Form Submission (redacted)");
+                    e.description(format!(
+                        "The applicant asked for their answers to be deleted on <t:{}:f>.",
+                        redacted_at
+                    ));
+                    e.field("Decision", submission.status.as_str(), true);
+                    e.field("Decided by", moderator, true);
+                    e.field("Decided on", decided, true);
+                    e.color(Color::DARK_GREY);
+                    e.footer(|f| {
+                        f.text(format!("Gotten UserId {}", submission.user_id));
+                        f
+                    });
+                    e
+                })
+            })
+            .await;
+    }
+}
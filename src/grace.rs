@@ -0,0 +1,291 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::{
+    client::Context,
+    model::{
+        guild::Member,
+        id::{ChannelId, GuildId, RoleId, UserId},
+        Timestamp,
+    },
+    utils::Color,
+};
+use sqlx::SqlitePool;
+
+use crate::bot::Bot;
+use crate::shutdown::Coordinator;
+use crate::structs::GracePeriodSettings;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+struct WaitingMember {
+    guild_id: i64,
+    user_id: i64,
+    joined_at: i64,
+}
+
+impl Bot {
+    pub async fn track_join(&self, member: &Member) {
+        if member.user.bot {
+            return;
+        }
+
+        let gid = member.guild_id.0 as i64;
+        let uid = member.user.id.0 as i64;
+        let joined_at = member
+            .joined_at
+            .unwrap_or_else(Timestamp::now)
+            .unix_timestamp();
+
+        if let Err(why) = crate::metrics::db(
+            "track_join",
+            sqlx::query!(
+                "INSERT OR REPLACE INTO member_joins (guild_id, user_id, joined_at, status) VALUES (?, ?, ?, 'waiting')",
+                gid,
+                uid,
+                joined_at
+            )
+            .execute(&self.database),
+        )
+        .await
+        {
+            tracing::error!("Could not track join of {}: {:?}", member.user.tag(), why);
+        }
+    }
+
+    /// Members who joined before joins were tracked get a row here too, so a later rejoin knows when they left.
+    pub async fn track_leave(&self, guild_id: GuildId, user_id: UserId, member: Option<&Member>) {
+        let gid = guild_id.0 as i64;
+        let uid = user_id.0 as i64;
+        let left_at = Timestamp::now().unix_timestamp();
+        let joined_at = member
+            .and_then(|m| m.joined_at)
+            .map_or(left_at, |t| t.unix_timestamp());
+        if let Err(why) = crate::metrics::db(
+            "track_leave",
+            sqlx::query!(
+                "INSERT INTO member_joins (guild_id, user_id, joined_at, status, left_at) VALUES (?, ?, ?, 'left', ?)
+                ON CONFLICT (guild_id, user_id) DO UPDATE SET
+                    left_at = excluded.left_at,
+                    status = CASE WHEN status IN ('waiting', 'reminded') THEN 'left' ELSE status END",
+                gid,
+                uid,
+                joined_at,
+                left_at
+            )
+            .execute(&self.database),
+        )
+        .await
+        {
+            tracing::error!("Could not track leave of {}: {:?}", uid, why);
+        }
+    }
+
+    pub fn grace_period_job(&self) -> GracePeriodJob {
+        GracePeriodJob {
+            database: self.database.clone(),
+            shutdown: self.shutdown.clone(),
+            settings: self.grace.clone(),
+            default_member_role: self.roles.default_member_role,
+            log_channel: self.responses_channel,
+            form_url: self.form_url.clone(),
+        }
+    }
+}
+
+/// Reminds members who never filled out the form and eventually kicks them.
+pub struct GracePeriodJob {
+    database: SqlitePool,
+    shutdown: Arc<Coordinator>,
+    settings: GracePeriodSettings,
+    default_member_role: RoleId,
+    log_channel: ChannelId,
+    form_url: Option<String>,
+}
+
+impl GracePeriodJob {
+    pub async fn run(self, ctx: Context) {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        while let Some(_running) = self.shutdown.tick(&mut interval).await {
+            if let Err(why) = self.send_reminders(&ctx).await {
+                tracing::error!("Could not send verification reminders: {:?}", why);
+            }
+            if let Err(why) = self.kick_unverified(&ctx).await {
+                tracing::error!("Could not kick unverified members: {:?}", why);
+            }
+        }
+    }
+
+    async fn set_status(&self, member: &WaitingMember, status: &str) -> Result<(), sqlx::Error> {
+        sqlx::query!(
+            "UPDATE member_joins SET status = ? WHERE guild_id = ? AND user_id = ?",
+            status,
+            member.guild_id,
+            member.user_id
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Looks the member up again and settles them if they no longer need to be chased.
+    async fn still_unverified(&self, ctx: &Context, waiting: &WaitingMember) -> Result<Option<Member>, sqlx::Error> {
+        let member = match ctx
+            .http
+            .get_member(waiting.guild_id as u64, waiting.user_id as u64)
+            .await
+        {
+            Ok(m) => m,
+            Err(_) => {
+                self.set_status(waiting, "left").await?;
+                return Ok(None);
+            }
+        };
+
+        if member.roles.contains(&self.default_member_role) {
+            self.set_status(waiting, "verified").await?;
+            return Ok(None);
+        }
+        if member.roles.iter().any(|r| self.settings.exempt_roles.contains(r)) {
+            self.set_status(waiting, "exempt").await?;
+            return Ok(None);
+        }
+
+        Ok(Some(member))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn send_reminders(&self, ctx: &Context) -> Result<(), sqlx::Error> {
+        if self.settings.remind_after_hours <= 0 {
+            return Ok(());
+        }
+
+        let cutoff = Timestamp::now().unix_timestamp() - self.settings.remind_after_hours * 60 * 60;
+        let due = sqlx::query_as!(
+            WaitingMember,
+            "SELECT guild_id, user_id, joined_at FROM member_joins
+            WHERE status = 'waiting' AND joined_at <= ?
+              AND NOT EXISTS (
+                  SELECT 1 FROM formanswers
+                  WHERE formanswers.user_id = member_joins.user_id
+                    AND formanswers.guild_id = member_joins.guild_id
+                    AND formanswers.submitted_at >= member_joins.joined_at
+              )",
+            cutoff
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        for waiting in due.iter() {
+            let member = match self.still_unverified(ctx, waiting).await? {
+                Some(m) => m,
+                None => continue,
+            };
+
+            let mut text = format!(
+                "Hi! You joined our server <t:{}:R> but haven't filled out the verification form yet.",
+                waiting.joined_at
+            );
+            if let Some(url) = &self.form_url {
+                text.push_str(&format!(" You can find it here: {}", url));
+            }
+            if self.settings.kick_after_days > 0 {
+                text.push_str(&format!(
+                    "\nMembers who are not verified after {} days are removed from the server.",
+                    self.settings.kick_after_days
+                ));
+            }
+
+            let delivered = member.user.direct_message(ctx, |m| m.content(&text)).await.is_ok();
+
+            let now = Timestamp::now().unix_timestamp();
+            sqlx::query!(
+                "UPDATE member_joins SET status = 'reminded', reminded_at = ? WHERE guild_id = ? AND user_id = ?",
+                now,
+                waiting.guild_id,
+                waiting.user_id
+            )
+            .execute(&self.database)
+            .await?;
+
+            tracing::info!("Reminded {} to fill out the form (delivered: {})", member.user.tag(), delivered);
+            let _ = self
+                .log_channel
+                .send_message(ctx, |f| {
+                    f.embed(|e| {
+                        e.title("Verification reminder");
+                        e.description(format!(
+                            "<@{}> joined <t:{}:R> and has not filled out the form.",
+                            waiting.user_id, waiting.joined_at
+                        ));
+                        e.field("DM delivered", if delivered { "Yes" } else { "No" }, true);
+                        e.color(Color::ORANGE);
+                        e
+                    })
+                })
+                .await;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn kick_unverified(&self, ctx: &Context) -> Result<(), sqlx::Error> {
+        if self.settings.kick_after_days <= 0 {
+            return Ok(());
+        }
+
+        let cutoff = Timestamp::now().unix_timestamp() - self.settings.kick_after_days * 24 * 60 * 60;
+        let due = sqlx::query_as!(
+            WaitingMember,
+            "SELECT guild_id, user_id, joined_at FROM member_joins
+            WHERE status IN ('waiting', 'reminded') AND joined_at <= ?
+              AND NOT EXISTS (
+                  SELECT 1 FROM formanswers
+                  WHERE formanswers.user_id = member_joins.user_id
+                    AND formanswers.guild_id = member_joins.guild_id
+                    AND formanswers.submitted_at >= member_joins.joined_at
+              )",
+            cutoff
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        for waiting in due.iter() {
+            let member = match self.still_unverified(ctx, waiting).await? {
+                Some(m) => m,
+                None => continue,
+            };
+
+            let reason = format!(
+                "Did not fill out the verification form within {} days",
+                self.settings.kick_after_days
+            );
+            if let Err(why) = member.kick_with_reason(ctx, &reason).await {
+                tracing::error!("Could not kick {}: {:?}", member.user.tag(), why);
+                continue;
+            }
+            self.set_status(waiting, "kicked").await?;
+
+            tracing::info!("Kicked {}: {}", member.user.tag(), reason);
+            let _ = self
+                .log_channel
+                .send_message(ctx, |f| {
+                    f.embed(|e| {
+                        e.title("Unverified member kicked");
+                        e.description(format!(
+                            "{} (<@{}>) joined <t:{}:R> and never filled out the form.",
+                            member.user.tag(),
+                            waiting.user_id,
+                            waiting.joined_at
+                        ));
+                        e.color(Color::DARK_RED);
+                        e
+                    })
+                })
+                .await;
+        }
+
+        Ok(())
+    }
+}
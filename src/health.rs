@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::Serialize;
+use serenity::{async_trait, client::Context, client::RawEventHandler, gateway::ConnectionStage, model::event::Event};
+use serenity::model::Timestamp;
+
+use crate::http::HttpState;
+
+const DB_PING_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Remembers when the gateway last sent us anything.
+#[derive(Clone, Default)]
+pub struct GatewayActivity {
+    pub last_event_at: Arc<AtomicI64>,
+}
+
+#[async_trait]
+impl RawEventHandler for GatewayActivity {
+    async fn raw_event(&self, _ctx: Context, _event: Event) {
+        self.last_event_at.store(Timestamp::now().unix_timestamp(), Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize)]
+struct ShardStatus {
+    id: u64,
+    stage: String,
+    latency_ms: Option<u128>,
+}
+
+#[derive(Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    shards: Vec<ShardStatus>,
+    database: String,
+    seconds_since_last_event: Option<i64>,
+}
+
+/// Ready means every shard is connected and keeping up its heartbeat, and the database answers.
+/// The gateway going quiet only counts if `max_event_age_secs` is set, a small server can be silent for hours.
+pub async fn readiness(state: &HttpState) -> Readiness {
+    let mut shards = Vec::new();
+    let mut shards_ready;
+    {
+        let manager = state.shard_manager.lock().await;
+        let runners = manager.runners.lock().await;
+        shards_ready = !runners.is_empty();
+        for (id, runner) in runners.iter() {
+            // a shard whose heartbeat goes unacknowledged is reconnected and leaves this stage
+            shards_ready &= runner.stage == ConnectionStage::Connected;
+            shards.push(ShardStatus {
+                id: id.0,
+                stage: runner.stage.to_string(),
+                latency_ms: runner.latency.map(|l| l.as_millis()),
+            });
+        }
+    }
+    shards.sort_by_key(|s| s.id);
+
+    let ping = tokio::time::timeout(DB_PING_TIMEOUT, sqlx::query("SELECT 1").execute(&state.database)).await;
+    let database = match ping {
+        Ok(Ok(_)) => "ok".to_string(),
+        Ok(Err(why)) => format!("error: {}", why),
+        Err(_) => "error: timed out".to_string(),
+    };
+
+    let last_event_at = state.activity.last_event_at.load(Ordering::Relaxed);
+    let seconds_since_last_event = (last_event_at > 0).then(|| Timestamp::now().unix_timestamp() - last_event_at);
+    let events_ready = state.max_event_age_secs <= 0
+        || seconds_since_last_event.is_some_and(|age| age <= state.max_event_age_secs);
+
+    Readiness {
+        ready: shards_ready && database == "ok" && events_ready,
+        shards,
+        database,
+        seconds_since_last_event,
+    }
+}
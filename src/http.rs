@@ -0,0 +1,58 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::prelude::Mutex;
+use sqlx::SqlitePool;
+
+/// What the endpoints need to look at, cloned into every request.
+#[derive(Clone)]
+pub struct HttpState {
+    pub database: SqlitePool,
+    pub shard_manager: Arc<Mutex<ShardManager>>,
+    pub activity: crate::health::GatewayActivity,
+    /// `/readyz` fails once the gateway was silent for longer than this, `0` turns the check off.
+    pub max_event_age_secs: i64,
+}
+
+async fn handle(state: HttpState, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    let response = match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => Response::builder()
+            .header("Content-Type", prometheus::TEXT_FORMAT)
+            .body(Body::from(crate::metrics::render(&state.database, &state.shard_manager).await)),
+        (&Method::GET, "/healthz") => Response::builder().body(Body::from("ok")),
+        (&Method::GET, "/readyz") => {
+            let readiness = crate::health::readiness(&state).await;
+            Response::builder()
+                .status(if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE })
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&readiness).unwrap_or_default()))
+        }
+        _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::from("Not found")),
+    };
+    Ok(response.unwrap_or_else(|_| Response::new(Body::empty())))
+}
+
+pub async fn serve(addr: SocketAddr, state: HttpState) {
+    let make_service = make_service_fn(move |_| {
+        let state = state.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| handle(state.clone(), req))) }
+    });
+
+    let server = match Server::try_bind(&addr) {
+        Ok(s) => s,
+        Err(why) => {
+            tracing::error!("Could not listen on {}: {:?}", addr, why);
+            return;
+        }
+    };
+    tracing::info!("Serving metrics and health checks on http://{}", addr);
+    if let Err(why) = server.serve(make_service).await {
+        tracing::error!("HTTP server error: {:?}", why);
+    }
+}
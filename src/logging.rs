@@ -0,0 +1,18 @@
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Sets up log output. `RUST_LOG` takes precedence over `LOG_LEVEL` from the config file,
+/// `LOG_FORMAT` picks `text` (default), `pretty` or `json`.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string())))
+        .expect("LOG_LEVEL invalid");
+
+    // keep stdout free for subcommands that print data
+    let builder = fmt().with_writer(std::io::stderr).with_env_filter(filter);
+    match std::env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => builder.json().with_current_span(true).with_span_list(true).init(),
+        Ok("pretty") => builder.pretty().init(),
+        Ok("text") | Err(_) => builder.init(),
+        Ok(other) => panic!("LOG_FORMAT invalid: {}", other),
+    }
+}
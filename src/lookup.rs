@@ -0,0 +1,101 @@
+use serenity::{
+    client::Context,
+    model::interactions::{
+        application_command::{
+            ApplicationCommandInteraction,
+            ApplicationCommandInteractionDataOptionValue as OptionValue,
+        },
+        InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+    },
+    utils::Color,
+};
+
+use crate::bot::{Bot, FormAnswersDB};
+
+/// Discord allows at most 25 fields per embed, older history is rarely needed anyway.
+const MAX_SUBMISSIONS: usize = 10;
+
+impl Bot {
+    pub async fn lookup_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        if !crate::commands::is_moderator(cmd) {
+            crate::commands::respond_error(ctx, cmd, "Only moderators can look up members.").await;
+            return;
+        }
+
+        let user = match cmd.data.options.iter().find(|o| o.name == "user").and_then(|o| o.resolved.as_ref()) {
+            Some(OptionValue::User(u, _)) => u.clone(),
+            _ => {
+                crate::commands::respond_error(ctx, cmd, "Pick the member to look up.").await;
+                return;
+            }
+        };
+
+        let uid = user.id.0 as i64;
+        let submissions = match sqlx::query_as!(
+            FormAnswersDB,
+            "SELECT * FROM formanswers WHERE user_id = ? ORDER BY submitted_at DESC, rowid DESC",
+            uid
+        )
+        .fetch_all(&self.database)
+        .await
+        {
+            Ok(s) => s,
+            Err(why) => {
+                tracing::error!("Could not look up submissions of {}: {:?}", uid, why);
+                crate::commands::respond_error(ctx, cmd, "Could not reach the database.").await;
+                return;
+            }
+        };
+
+        let notes = self.notes_for(uid).await;
+
+        let guild = cmd.guild_id.map(|g| g.0.to_string()).unwrap_or_else(|| "@me".to_string());
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.embed(|e| {
+                        e.title(format!("Submissions of {}", user.tag()));
+                        e.color(Color::BLURPLE);
+                        if submissions.is_empty() && notes.is_empty() {
+                            e.description("No submissions or notes on record.");
+                        } else if submissions.len() > MAX_SUBMISSIONS {
+                            e.description(format!(
+                                "Showing the latest {} of {} submissions.",
+                                MAX_SUBMISSIONS,
+                                submissions.len()
+                            ));
+                        }
+
+                        for s in submissions.iter().take(MAX_SUBMISSIONS) {
+                            let mut value = format!(
+                                "[Review message](https://discord.com/channels/{}/{}/{})",
+                                guild, self.responses_channel.0, s.message_id
+                            );
+                            if let Some(thread) = s.thread_id {
+                                value.push_str(&format!(" · Discussion: <#{}>", thread));
+                            }
+                            if let Some(ts) = s.submitted_at {
+                                value.push_str(&format!("\nSubmitted <t:{}:f>", ts));
+                            }
+                            if let (Some(ts), Some(moderator)) = (s.decided_at, s.moderator_id) {
+                                value.push_str(&format!("\nDecided <t:{}:f> by <@{}>", ts, moderator));
+                            }
+                            if let (Some(ts), Some(moderator)) = (s.revoked_at, s.revoked_by) {
+                                value.push_str(&format!("\nRevoked <t:{}:f> by <@{}>", ts, moderator));
+                            }
+
+                            let kind = if s.kind == "update" { "Update request" } else { "Submission" };
+                            e.field(format!("{}: {}", kind, s.status), value, false);
+                        }
+                        if !notes.is_empty() {
+                            e.field(crate::notes::NOTES_FIELD, crate::notes::format_notes(&notes), false);
+                        }
+                        e
+                    })
+                })
+            })
+            .await;
+    }
+}
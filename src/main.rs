@@ -1,16 +1,42 @@
+mod admin;
+mod aging;
+mod alts;
+mod audit;
 mod bot;
+mod cli;
+mod commands;
+mod duplicates;
+mod embeds;
+mod export;
+mod external;
+mod forget;
+mod grace;
+mod health;
+mod http;
+mod logging;
+mod lookup;
+mod metrics;
+mod notes;
+mod rejoin;
+mod reverify;
+mod revoke;
+mod risk;
+mod shutdown;
+mod sla;
+mod stats;
 mod structs;
+mod threads;
+mod validation;
+mod welcome;
 
 use std::time::Duration;
-use sqlx::Pool;
-use bot::Bot;
 use serenity::client::bridge::gateway::ShardManager;
-pub type Error = Box<dyn std::error::Error>;
-use serenity::{client::ClientBuilder, prelude::GatewayIntents};
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+use clap::Parser;
+use serenity::prelude::GatewayIntents;
 use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions};
-use std::sync::{
-    Arc, Mutex
-};
+use sqlx::SqlitePool;
+use std::sync::Arc;
 
 use serenity::prelude::Mutex as Gaytex;
 
@@ -18,6 +44,8 @@ const INTENTS: GatewayIntents = GatewayIntents::from_bits_truncate(
     GatewayIntents::DIRECT_MESSAGES.bits()
         | GatewayIntents::GUILD_MESSAGES.bits()
         | GatewayIntents::GUILDS.bits()
+        | GatewayIntents::GUILD_MEMBERS.bits()
+        | GatewayIntents::GUILD_BANS.bits()
         | GatewayIntents::MESSAGE_CONTENT.bits(),
 );
 
@@ -36,15 +64,13 @@ impl serenity::prelude::TypeMapKey for DBPool {
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     dotenv::dotenv().ok();
-    tracing_subscriber::fmt().init();
-    let token = std::env::var("DISCORD_TOKEN").expect("DISCORD_TOKEN missing");
-    let appid: u64 = std::env::var("DISCORD_APPID")
-        .expect("DISCORD_APPID missing").parse().expect("DISCORD_APPID invalid");
+    logging::init();
+    let args = cli::Cli::parse();
 
     let sql = {
         let opts = SqliteConnectOptions::new()
             .create_if_missing(true)
-            .filename("bot.db")
+            .filename(&args.database)
             .journal_mode(SqliteJournalMode::Wal);
         let pool = SqlitePoolOptions::new()
             .max_lifetime(Duration::from_secs(3600))
@@ -57,6 +83,26 @@ async fn main() -> Result<(), Error> {
        pool
     };
 
+    match args.command.unwrap_or(cli::Command::Run) {
+        cli::Command::Run => run(sql).await,
+        cli::Command::Migrate => {
+            // migrations already ran while connecting
+            eprintln!("Database is up to date");
+            Ok(())
+        }
+        cli::Command::Export(export_args) => export::export_cli(&sql, export_args).await,
+        cli::Command::Import(import_args) => export::import_cli(&sql, import_args).await,
+        cli::Command::Purge(purge_args) => admin::purge(&sql, purge_args).await,
+        cli::Command::Show { user_id } => admin::show(&sql, user_id).await,
+        cli::Command::SetStatus { message_id, status } => admin::set_status(&sql, message_id, status).await,
+    }
+}
+
+async fn run(sql: SqlitePool) -> Result<(), Error> {
+    let token = std::env::var("DISCORD_TOKEN").expect("DISCORD_TOKEN missing");
+    let appid: u64 = std::env::var("DISCORD_APPID")
+        .expect("DISCORD_APPID missing").parse().expect("DISCORD_APPID invalid");
+
     let roles = structs::GuildRoleSettings {
         boomer:              serenity::model::id::RoleId(877611738198069338),
         fussvolk:            serenity::model::id::RoleId(877610678704308256),
@@ -69,15 +115,97 @@ async fn main() -> Result<(), Error> {
     };
 
 
+    let welcome = structs::WelcomeSettings {
+        dm_template: std::env::var("WELCOME_DM_TEMPLATE")
+            .unwrap_or_else(|_| welcome::DEFAULT_TEMPLATE.to_string()),
+        channel: std::env::var("WELCOME_CHANNEL_ID")
+            .ok()
+            .map(|id| serenity::model::id::ChannelId(id.parse().expect("WELCOME_CHANNEL_ID invalid"))),
+        channel_template: std::env::var("WELCOME_CHANNEL_TEMPLATE")
+            .unwrap_or_else(|_| welcome::DEFAULT_CHANNEL_TEMPLATE.to_string()),
+    };
+
+    let grace = structs::GracePeriodSettings {
+        remind_after_hours: env_or("REMINDER_AFTER_HOURS", 24),
+        kick_after_days: env_or("KICK_AFTER_DAYS", 7),
+        exempt_roles: std::env::var("UNVERIFIED_EXEMPT_ROLES")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| serenity::model::id::RoleId(id.trim().parse().expect("UNVERIFIED_EXEMPT_ROLES invalid")))
+            .collect(),
+    };
+
+    let risk = structs::RiskSettings {
+        new_account_days: env_or("RISK_NEW_ACCOUNT_DAYS", 30),
+        weight_new_account: env_or("RISK_WEIGHT_NEW_ACCOUNT", 3),
+        weight_no_avatar: env_or("RISK_WEIGHT_NO_AVATAR", 1),
+        weight_prior_submission: env_or("RISK_WEIGHT_PRIOR_SUBMISSION", 1),
+        weight_prior_rejection: env_or("RISK_WEIGHT_PRIOR_REJECTION", 3),
+        warn_threshold: env_or("RISK_WARN_THRESHOLD", 3),
+        danger_threshold: env_or("RISK_DANGER_THRESHOLD", 5),
+        alt_match_threshold: env_or("ALT_MATCH_THRESHOLD", 0.85),
+    };
+
+    let age_transitions = structs::AgeTransitionSettings {
+        require_confirmation: env_or("AGE_TRANSITION_CONFIRM", false),
+    };
+
+    let rejoin = structs::RejoinSettings {
+        policy: env_or("REJOIN_POLICY", structs::RejoinPolicy::Restore),
+        max_absence_days: env_or("REJOIN_RESTORE_MAX_DAYS", 30),
+    };
+
+    let reports = structs::ReportSettings {
+        channel: std::env::var("STATS_CHANNEL_ID")
+            .ok()
+            .map(|id| serenity::model::id::ChannelId(id.parse().expect("STATS_CHANNEL_ID invalid"))),
+    };
+
+    let sla = structs::SlaSettings {
+        alert_after_hours: env_or("SLA_ALERT_AFTER_HOURS", 24),
+        alert_role: std::env::var("SLA_ALERT_ROLE_ID")
+            .ok()
+            .map(|id| serenity::model::id::RoleId(id.parse().expect("SLA_ALERT_ROLE_ID invalid"))),
+        escalate_after_hours: env_or("SLA_ESCALATE_AFTER_HOURS", 72),
+        escalate_role: std::env::var("SLA_ESCALATE_ROLE_ID")
+            .ok()
+            .map(|id| serenity::model::id::RoleId(id.parse().expect("SLA_ESCALATE_ROLE_ID invalid"))),
+    };
+
+    // serves /metrics, /healthz and /readyz, nothing is exposed unless this is set
+    let http_addr: Option<std::net::SocketAddr> = std::env::var("HTTP_ADDR")
+        .ok()
+        .map(|addr| addr.parse().expect("HTTP_ADDR invalid"));
+    // off by default, the heartbeat already tells whether the gateway is alive
+    let max_event_age_secs = env_or("READY_MAX_EVENT_AGE_SECS", 0);
+    let activity = health::GatewayActivity::default();
+
+    let database = sql.clone();
+    let shutdown = Arc::new(shutdown::Coordinator::default());
+    let shutdown_timeout = Duration::from_secs(env_or("SHUTDOWN_TIMEOUT_SECS", 30));
+
     let bot = bot::Bot {
         database: sql,
         roles,
         responses_channel:   serenity::model::id::ChannelId(968522899768094740),
+        welcome,
+        grace,
+        risk,
+        age_transitions,
+        rejoin,
+        reports,
+        sla,
+        form_url: std::env::var("FORM_URL").ok(),
+        jobs_started: std::sync::atomic::AtomicBool::new(false),
+        shutdown: shutdown.clone(),
     };
 
 
     let mut client = serenity::Client::builder(&token, INTENTS)
+        .application_id(appid)
         .event_handler(bot)
+        .raw_event_handler(activity.clone())
         .await
         .expect("Err creating client");
 
@@ -88,17 +216,37 @@ async fn main() -> Result<(), Error> {
 
     let shard_manager = client.shard_manager.clone();
 
+    if let Some(addr) = http_addr {
+        tokio::spawn(http::serve(addr, http::HttpState {
+            database: database.clone(),
+            shard_manager: shard_manager.clone(),
+            activity,
+            max_event_age_secs,
+        }));
+    }
+
+    // finish submissions and job runs that are under way before disconnecting
     tokio::spawn(async move {
-        tokio::signal::ctrl_c().await.expect("Could not register ctrl+c handler");
+        shutdown::signal().await;
+        tracing::info!("Shutting down, waiting up to {:?} for running work", shutdown_timeout);
+        if !shutdown.drain(shutdown_timeout).await {
+            tracing::warn!("Work was still running after {:?}, stopping anyway", shutdown_timeout);
+        }
         shard_manager.lock().await.shutdown_all().await;
     });
 
     if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+        tracing::error!("Client error: {:?}", why);
     }
 
+    database.close().await;
+    tracing::info!("Shut down");
+    Ok(())
+}
 
-
-
-        Ok(())
+fn env_or<T: std::str::FromStr>(key: &str, default: T) -> T {
+    match std::env::var(key) {
+        Ok(value) => value.parse().unwrap_or_else(|_| panic!("{} invalid", key)),
+        Err(_) => default,
+    }
 }
@@ -0,0 +1,106 @@
+use std::future::Future;
+
+use once_cell::sync::Lazy;
+use prometheus::{
+    register_gauge_vec, register_histogram_vec, register_int_counter, register_int_counter_vec,
+    register_int_gauge, register_int_gauge_vec, Encoder, GaugeVec, HistogramVec, IntCounter, IntCounterVec,
+    IntGauge, IntGaugeVec, TextEncoder,
+};
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::gateway::ConnectionStage;
+use serenity::prelude::Mutex;
+use sqlx::SqlitePool;
+use std::sync::Arc;
+
+pub static SUBMISSIONS_RECEIVED: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("verification_submissions_received_total", "Form submissions posted by the webhook").unwrap()
+});
+
+pub static PARSE_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("verification_parse_failures_total", "Form submissions whose answers could not be read").unwrap()
+});
+
+pub static MATCH_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!(
+        "verification_match_failures_total",
+        "Form submissions whose Discord tag matched no member"
+    )
+    .unwrap()
+});
+
+pub static DECISIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec!("verification_decisions_total", "Decisions on submissions by outcome", &["status"]).unwrap()
+});
+
+pub static ROLE_ASSIGNMENT_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register_int_counter!("verification_role_assignment_errors_total", "Roles that could not be given or taken").unwrap()
+});
+
+pub static DISCORD_API_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!("verification_discord_api_seconds", "Duration of Discord API calls", &["call"]).unwrap()
+});
+
+pub static DB_QUERY_SECONDS: Lazy<HistogramVec> = Lazy::new(|| {
+    register_histogram_vec!("verification_db_query_seconds", "Duration of database queries", &["query"]).unwrap()
+});
+
+static PENDING_SUBMISSIONS: Lazy<IntGauge> = Lazy::new(|| {
+    register_int_gauge!("verification_pending_submissions", "Submissions waiting for a decision").unwrap()
+});
+
+static SHARD_CONNECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec!("verification_shard_connected", "Whether a gateway shard is connected", &["shard"]).unwrap()
+});
+
+static SHARD_LATENCY_SECONDS: Lazy<GaugeVec> = Lazy::new(|| {
+    register_gauge_vec!("verification_shard_latency_seconds", "Gateway heartbeat latency of a shard", &["shard"])
+        .unwrap()
+});
+
+/// Times a Discord API call.
+pub async fn discord<T>(call: &str, fut: impl Future<Output = T>) -> T {
+    let _timer = DISCORD_API_SECONDS.with_label_values(&[call]).start_timer();
+    fut.await
+}
+
+/// Times a database query.
+pub async fn db<T>(query: &str, fut: impl Future<Output = T>) -> T {
+    let _timer = DB_QUERY_SECONDS.with_label_values(&[query]).start_timer();
+    fut.await
+}
+
+/// Updates the values that are read on demand instead of counted as they happen.
+async fn refresh(pool: &SqlitePool, shard_manager: &Arc<Mutex<ShardManager>>) {
+    let pending = db(
+        "count_pending",
+        sqlx::query!(r#"SELECT COUNT(*) AS "count!: i64" FROM formanswers WHERE status = 'pending'"#).fetch_one(pool),
+    )
+    .await;
+    match pending {
+        Ok(r) => PENDING_SUBMISSIONS.set(r.count),
+        Err(why) => tracing::error!("Could not count pending submissions: {:?}", why),
+    }
+
+    let manager = shard_manager.lock().await;
+    let runners = manager.runners.lock().await;
+    for (id, runner) in runners.iter() {
+        let shard = id.0.to_string();
+        SHARD_CONNECTED
+            .with_label_values(&[&shard])
+            .set((runner.stage == ConnectionStage::Connected) as i64);
+        if let Some(latency) = runner.latency {
+            SHARD_LATENCY_SECONDS.with_label_values(&[&shard]).set(latency.as_secs_f64());
+        }
+    }
+}
+
+/// Everything in the Prometheus text format.
+pub async fn render(pool: &SqlitePool, shard_manager: &Arc<Mutex<ShardManager>>) -> Vec<u8> {
+    refresh(pool, shard_manager).await;
+
+    let mut buffer = Vec::new();
+    if let Err(why) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        tracing::error!("Could not encode metrics: {:?}", why);
+    }
+    buffer
+}
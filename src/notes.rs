@@ -0,0 +1,249 @@
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::{
+        id::MessageId,
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction,
+                ApplicationCommandInteractionDataOptionValue as OptionValue,
+            },
+            message_component::{ActionRowComponent, InputTextStyle, MessageComponentInteraction},
+            modal::ModalSubmitInteraction,
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        Timestamp,
+    },
+    utils::Color,
+};
+
+use crate::bot::Bot;
+use crate::embeds::{truncate, MAX_FIELD_VALUE};
+
+pub const NOTES_FIELD: &str = "Moderator notes";
+pub const MAX_NOTE_LENGTH: u64 = 500;
+
+pub struct Note {
+    pub moderator_id: i64,
+    pub note: String,
+    pub created_at: i64,
+}
+
+/// Renders notes oldest first, dropping the oldest ones if they don't fit into one embed field.
+/// The newest note is always shown, cut short if it doesn't fit on its own.
+pub fn format_notes(notes: &[Note]) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let mut length = 0;
+    for n in notes.iter().rev() {
+        let line = format!("<t:{}:d> <@{}>: {}", n.created_at, n.moderator_id, n.note);
+        if length + line.len() + 1 > MAX_FIELD_VALUE {
+            if lines.is_empty() {
+                lines.push(truncate(&line, MAX_FIELD_VALUE));
+            }
+            break;
+        }
+        length += line.len() + 1;
+        lines.push(line);
+    }
+    lines.reverse();
+    lines.join("\n")
+}
+
+impl Bot {
+    pub async fn notes_for(&self, user_id: i64) -> Vec<Note> {
+        crate::metrics::db(
+            "notes_for",
+            sqlx::query_as!(
+                Note,
+                "SELECT moderator_id, note, created_at FROM moderator_notes WHERE user_id = ? ORDER BY created_at, id",
+                user_id
+            )
+            .fetch_all(&self.database),
+        )
+        .await
+        .unwrap_or_default()
+    }
+
+    async fn add_note(
+        &self,
+        guild_id: Option<i64>,
+        user_id: i64,
+        message_id: Option<i64>,
+        moderator_id: i64,
+        note: &str,
+    ) -> Result<(), sqlx::Error> {
+        let created_at = Timestamp::now().unix_timestamp();
+        sqlx::query!(
+            "INSERT INTO moderator_notes (guild_id, user_id, message_id, moderator_id, note, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+            guild_id,
+            user_id,
+            message_id,
+            moderator_id,
+            note,
+            created_at
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+
+    /// Replaces the notes field on a review message with the current notes.
+    async fn refresh_notes_field(&self, ctx: &Context, message_id: i64, user_id: i64) {
+        let message = match self.responses_channel.message(ctx, MessageId(message_id as u64)).await {
+            Ok(m) => m,
+            Err(_) => return,
+        };
+        let mut embed = match message.embeds.first() {
+            Some(e) => e.clone(),
+            None => return,
+        };
+        embed.fields.retain(|f| f.name != NOTES_FIELD);
+
+        let mut embed = CreateEmbed::from(embed);
+        let notes = self.notes_for(user_id).await;
+        if !notes.is_empty() {
+            embed.field(NOTES_FIELD, format_notes(&notes), false);
+        }
+        crate::embeds::fit(&mut embed);
+
+        let _ = self
+            .responses_channel
+            .edit_message(ctx, message.id, |m| m.set_embed(embed))
+            .await;
+    }
+
+    pub async fn add_note_button(&self, ctx: &Context, msgc: &MessageComponentInteraction) {
+        let _ = msgc
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::Modal);
+                f.interaction_response_data(|f| {
+                    f.custom_id("add_note_modal");
+                    f.title("Add a moderator note");
+                    f.components(|c| {
+                        c.create_action_row(|a| {
+                            a.create_input_text(|t| {
+                                t.custom_id("note");
+                                t.label("Note");
+                                t.style(InputTextStyle::Paragraph);
+                                t.max_length(MAX_NOTE_LENGTH);
+                                t.required(true)
+                            })
+                        })
+                    })
+                })
+            })
+            .await;
+    }
+
+    pub async fn note_modal(&self, ctx: &Context, modal: &ModalSubmitInteraction) {
+        let note = modal
+            .data
+            .components
+            .iter()
+            .flat_map(|row| row.components.iter())
+            .find_map(|c| match c {
+                ActionRowComponent::InputText(t) if t.custom_id == "note" => Some(t.value.trim().to_string()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let mid = modal.message.as_ref().map(|m| m.id.0 as i64).unwrap_or_default();
+        let submission = sqlx::query!("SELECT user_id, guild_id FROM formanswers WHERE message_id = ?", mid)
+            .fetch_optional(&self.database)
+            .await;
+
+        let text = match submission {
+            _ if note.is_empty() => "The note was empty, nothing was saved.".to_string(),
+            Ok(Some(s)) => {
+                let moderator_id = modal.user.id.0 as i64;
+                match self.add_note(s.guild_id, s.user_id, Some(mid), moderator_id, &note).await {
+                    Ok(()) => {
+                        self.refresh_notes_field(ctx, mid, s.user_id).await;
+                        "Note added.".to_string()
+                    }
+                    Err(why) => {
+                        tracing::error!("Could not add note to {}: {:?}", mid, why);
+                        "Could not save the note.".to_string()
+                    }
+                }
+            }
+            _ => "Could not find this submission in the database.".to_string(),
+        };
+
+        let _ = modal
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.content(text)
+                })
+            })
+            .await;
+    }
+
+    pub async fn note_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        if !crate::commands::is_moderator(cmd) {
+            crate::commands::respond_error(ctx, cmd, "Only moderators can add notes.").await;
+            return;
+        }
+
+        let mut user = None;
+        let mut note = String::new();
+        for option in cmd.data.options.iter() {
+            match (option.name.as_str(), option.resolved.as_ref()) {
+                ("user", Some(OptionValue::User(u, _))) => user = Some(u.clone()),
+                ("text", Some(OptionValue::String(s))) => note = s.trim().to_string(),
+                _ => {}
+            }
+        }
+        let user = match user {
+            Some(u) if !note.is_empty() => u,
+            _ => {
+                crate::commands::respond_error(ctx, cmd, "Pick a member and write a note.").await;
+                return;
+            }
+        };
+        if note.chars().count() as u64 > MAX_NOTE_LENGTH {
+            let message = format!("Notes can be at most {} characters long.", MAX_NOTE_LENGTH);
+            crate::commands::respond_error(ctx, cmd, &message).await;
+            return;
+        }
+
+        let uid = user.id.0 as i64;
+        let gid = cmd.guild_id.map(|g| g.0 as i64);
+        // attach the note to the latest submission, if there is one
+        let latest = sqlx::query!(
+            "SELECT message_id FROM formanswers WHERE user_id = ? ORDER BY submitted_at DESC, rowid DESC LIMIT 1",
+            uid
+        )
+        .fetch_optional(&self.database)
+        .await
+        .ok()
+        .flatten()
+        .map(|r| r.message_id);
+
+        if let Err(why) = self.add_note(gid, uid, latest, cmd.user.id.0 as i64, &note).await {
+            tracing::error!("Could not add note to {}: {:?}", uid, why);
+            crate::commands::respond_error(ctx, cmd, "Could not save the note.").await;
+            return;
+        }
+        if let Some(mid) = latest {
+            self.refresh_notes_field(ctx, mid, uid).await;
+        }
+
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.embed(|e| {
+                        e.title("Note added");
+                        e.description(format!("Added a note to {}.", user.tag()));
+                        e.color(Color::DARK_GREEN);
+                        e
+                    })
+                })
+            })
+            .await;
+    }
+}
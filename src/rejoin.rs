@@ -0,0 +1,326 @@
+use serenity::{
+    client::Context,
+    model::{
+        guild::Member,
+        id::{GuildId, RoleId},
+        interactions::{
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        Timestamp,
+    },
+    utils::Color,
+};
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::embeds::mention_roles;
+use crate::structs::{RejoinPolicy, SubmissionStatus};
+
+fn is_approval(status: &str) -> bool {
+    status.parse::<SubmissionStatus>().map(|s| s.is_approval()).unwrap_or(false)
+}
+
+/// The approval the member's roles still rest on, if no later kick, ban or revocation undid it.
+/// Rejected update requests are skipped, the member stays verified when those are turned down.
+fn standing_approval(previous: &[FormAnswersDB]) -> Option<&FormAnswersDB> {
+    previous
+        .iter()
+        .filter(|s| s.status != SubmissionStatus::Pending.as_str() && s.status != SubmissionStatus::Superseded.as_str())
+        .find(|s| !(s.kind == "update" && s.status == SubmissionStatus::Rejected.as_str()))
+        .filter(|s| is_approval(&s.status))
+}
+
+impl Bot {
+    async fn previous_submissions(&self, guild_id: GuildId, user_id: i64) -> Vec<FormAnswersDB> {
+        let gid = guild_id.0 as i64;
+        sqlx::query_as!(
+            FormAnswersDB,
+            "SELECT * FROM formanswers WHERE user_id = ?1 AND (guild_id = ?2 OR guild_id IS NULL)
+            ORDER BY submitted_at DESC, rowid DESC",
+            user_id,
+            gid
+        )
+        .fetch_all(&self.database)
+        .await
+        .unwrap_or_default()
+    }
+
+    /// Gives a returning member the roles their approved submission granted.
+    async fn restore_roles(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        member: &mut Member,
+        approved: &FormAnswersDB,
+    ) -> Result<Vec<RoleId>, crate::Error> {
+        let gid = guild_id.0 as i64;
+        let tracked = sqlx::query!(
+            "SELECT DISTINCT role_id FROM granted_roles WHERE guild_id = ? AND user_id = ? AND removed_at IS NULL",
+            gid,
+            approved.user_id
+        )
+        .fetch_all(&self.database)
+        .await?;
+        let roles: Vec<RoleId> = if tracked.is_empty() {
+            self.roles.for_answers(
+                approved.is_female,
+                approved.is_18_plus,
+                approved.is_30_plus,
+                approved.diagnosis_status.as_deref().unwrap_or_default(),
+            )
+        } else {
+            tracked.iter().map(|r| RoleId(r.role_id as u64)).collect()
+        };
+
+        let missing: Vec<RoleId> = roles.iter().filter(|r| !member.roles.contains(r)).copied().collect();
+        if !missing.is_empty() {
+            crate::metrics::discord("add_roles", member.add_roles(ctx, &missing)).await
+                .inspect_err(|_| crate::metrics::ROLE_ASSIGNMENT_ERRORS.inc())?;
+        }
+        Ok(missing)
+    }
+
+    /// Posts what we know about a member who joins again and applies the rejoin policy.
+    pub async fn returning_applicant(&self, ctx: &Context, member: &Member) {
+        if member.user.bot {
+            return;
+        }
+
+        let uid = member.user.id.0 as i64;
+        let previous = self.previous_submissions(member.guild_id, uid).await;
+        let latest = match previous.first() {
+            Some(l) => l,
+            None => return,
+        };
+        let decided = previous.iter().find(|s| {
+            s.status != SubmissionStatus::Pending.as_str() && s.status != SubmissionStatus::Superseded.as_str()
+        });
+        let approved = standing_approval(&previous);
+
+        let gid = member.guild_id.0 as i64;
+        let left_at = sqlx::query!("SELECT left_at FROM member_joins WHERE guild_id = ? AND user_id = ?", gid, uid)
+            .fetch_optional(&self.database)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|r| r.left_at);
+        let absence_days = left_at.map(|ts| (Timestamp::now().unix_timestamp() - ts) / (24 * 60 * 60));
+        // without a recorded leave we can't tell how long they were gone, so a moderator decides
+        let within_limit = match absence_days {
+            Some(days) => self.rejoin.max_absence_days <= 0 || days <= self.rejoin.max_absence_days,
+            None => false,
+        };
+
+        let mut restored = None;
+        if let (Some(approved), RejoinPolicy::Restore, true) = (approved, self.rejoin.policy, within_limit) {
+            let mut member = member.clone();
+            restored = Some(match self.restore_roles(ctx, member.guild_id, &mut member, approved).await {
+                Ok(roles) => {
+                    crate::audit::record(
+                        &self.database,
+                        gid,
+                        uid,
+                        None,
+                        "restore_roles",
+                        Some(&format!("Restored on rejoin: {}", mention_roles(&roles))),
+                    )
+                    .await;
+                    format!("Restored automatically: {}", mention_roles(&roles))
+                }
+                Err(why) => {
+                    tracing::error!("Could not restore roles of {}: {:?}", member.user.tag(), why);
+                    "Could not restore the roles, please add them by hand".to_string()
+                }
+            });
+        }
+        let ask_moderator = approved.is_some()
+            && match self.rejoin.policy {
+                RejoinPolicy::Notify => false,
+                RejoinPolicy::Review => true,
+                RejoinPolicy::Restore => !within_limit,
+            };
+        if approved.is_some() && self.rejoin.policy == RejoinPolicy::Restore && !within_limit {
+            restored = Some(match absence_days {
+                Some(days) => format!(
+                    "Not restored automatically, they were away for {} days (limit: {} days)",
+                    days, self.rejoin.max_absence_days
+                ),
+                None => "Not restored automatically, it is unknown how long they were away".to_string(),
+            });
+        }
+
+        let _ = self
+            .responses_channel
+            .send_message(ctx, |f| {
+                f.content(format!("User Mention: <@{}>", uid));
+                f.embed(|e| {
+                    e.title("Returning applicant");
+                    e.description(format!(
+                        "{} joined the server again. They submitted the form {} time(s) before.",
+                        member.user.tag(),
+                        previous.len()
+                    ));
+                    e.field(
+                        "Last submission",
+                        format!(
+                            "{}, submitted {} ([review message](https://discord.com/channels/{}/{}/{}))",
+                            latest.status,
+                            latest.submitted_at.map(|ts| format!("<t:{}:f>", ts)).unwrap_or_else(|| "at an unknown date".to_string()),
+                            gid,
+                            self.responses_channel.0,
+                            latest.message_id
+                        ),
+                        false,
+                    );
+                    if let Some(decided) = decided {
+                        e.field(
+                            "Last decision",
+                            format!(
+                                "{} by {} on {}",
+                                decided.status,
+                                decided.moderator_id.map(|id| format!("<@{}>", id)).unwrap_or_else(|| "unknown".to_string()),
+                                decided.decided_at.map(|ts| format!("<t:{}:f>", ts)).unwrap_or_else(|| "an unknown date".to_string())
+                            ),
+                            false,
+                        );
+                    }
+                    if let Some(left_at) = left_at {
+                        e.field("Left", format!("<t:{}:R>", left_at), true);
+                    }
+                    if let Some(thread) = latest.thread_id {
+                        e.field("Discussion", format!("<#{}>", thread), true);
+                    }
+                    if let Some(restored) = &restored {
+                        e.field("Roles", restored, false);
+                    }
+                    e.color(match approved.or(decided).map(|d| d.status.as_str()) {
+                        Some("approved") | Some("approved_manually") => Color::DARK_GREEN,
+                        Some("kicked") | Some("rejected") => Color::ORANGE,
+                        Some("banned") | Some("revoked") => Color::RED,
+                        _ => Color::BLURPLE,
+                    });
+                    e.footer(|f| {
+                        f.text(format!("Gotten UserId {}", uid));
+                        f
+                    });
+                    e
+                });
+                if ask_moderator {
+                    f.components(|c| {
+                        c.create_action_row(|a| {
+                            a.create_button(|b| {
+                                b.label("Restore roles");
+                                b.style(ButtonStyle::Success);
+                                b.custom_id(format!("rejoin_restore_{}", uid));
+                                b
+                            });
+                            a.create_button(|b| {
+                                b.label("Require new submission");
+                                b.style(ButtonStyle::Secondary);
+                                b.custom_id(format!("rejoin_rereview_{}", uid));
+                                b
+                            })
+                        })
+                    });
+                }
+                f
+            })
+            .await;
+    }
+
+    /// Handles the moderator's choice on a returning applicant notice.
+    pub async fn rejoin_button(&self, ctx: &Context, msgc: &MessageComponentInteraction) {
+        let (action, uid) = match msgc.data.custom_id.rsplit_once('_') {
+            Some((action, uid)) => (action, uid.parse::<i64>().unwrap_or_default()),
+            None => return,
+        };
+        let guild_id = match msgc.guild_id {
+            Some(g) => g,
+            None => return,
+        };
+
+        let _ = msgc
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::DeferredChannelMessageWithSource);
+                f.interaction_response_data(|f| f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL))
+            })
+            .await;
+
+        let result: Result<(&str, String), crate::Error> = async {
+            let mut member = guild_id.member(ctx, uid as u64).await?;
+            if action == "rejoin_restore" {
+                let previous = self.previous_submissions(guild_id, uid).await;
+                let approved = standing_approval(&previous).ok_or("The member has no approved submission anymore")?;
+                let roles = self.restore_roles(ctx, guild_id, &mut member, approved).await?;
+                Ok(("Roles restored", format!("Restored {}", mention_roles(&roles))))
+            } else {
+                let mut text = "Welcome back! Please fill out the verification form again so our moderators can review it.".to_string();
+                if let Some(url) = &self.form_url {
+                    text.push_str(&format!(" You can find it here: {}", url));
+                }
+                let delivered = member.user.direct_message(ctx, |m| m.content(&text)).await.is_ok();
+                Ok((
+                    "New submission required",
+                    format!("Asked the member to submit the form again (DM delivered: {})", if delivered { "Yes" } else { "No" }),
+                ))
+            }
+        }
+        .await;
+
+        let (label, summary) = match result {
+            Ok(r) => r,
+            Err(why) => {
+                let _ = msgc
+                    .edit_original_interaction_response(ctx, |f| {
+                        f.embed(|e| {
+                            e.title("Error");
+                            e.description(why.to_string());
+                            e.color(Color::DARK_RED);
+                            e
+                        })
+                    })
+                    .await;
+                return;
+            }
+        };
+
+        crate::audit::record(
+            &self.database,
+            guild_id.0 as i64,
+            uid,
+            Some(msgc.user.id.0 as i64),
+            if action == "rejoin_restore" { "restore_roles" } else { "require_resubmission" },
+            Some(&summary),
+        )
+        .await;
+
+        let _ = msgc
+            .edit_original_interaction_response(ctx, |f| {
+                f.embed(|e| {
+                    e.title(label);
+                    e.description(&summary);
+                    e.color(Color::DARK_GREEN);
+                    e
+                })
+            })
+            .await;
+
+        let _ = msgc
+            .message
+            .clone()
+            .edit(ctx, |m| {
+                m.components(|c| {
+                    c.create_action_row(|a| {
+                        a.create_button(|b| {
+                            b.label(format!("{} by {}", label, msgc.user.tag()));
+                            b.style(ButtonStyle::Secondary);
+                            b.custom_id("rejoin_decided");
+                            b.disabled(true);
+                            b
+                        })
+                    })
+                })
+            })
+            .await;
+    }
+}
@@ -0,0 +1,277 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    model::{
+        id::{EmojiId, RoleId},
+        interactions::{
+            application_command::ApplicationCommandInteraction,
+            message_component::{ButtonStyle, MessageComponentInteraction},
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        prelude::ReactionType,
+    },
+    utils::Color,
+};
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::embeds::mention_roles;
+use crate::structs::SubmissionStatus;
+
+/// Roles to add and remove when a verified member's answers change.
+pub struct RoleDiff {
+    pub add: Vec<RoleId>,
+    pub remove: Vec<RoleId>,
+}
+
+impl RoleDiff {
+    /// Only roles the bot manages are ever removed, anything a moderator gave out by hand stays.
+    pub fn new(current: &[RoleId], wanted: &[RoleId], managed: &[RoleId]) -> RoleDiff {
+        RoleDiff {
+            add: wanted.iter().filter(|r| !current.contains(r)).copied().collect(),
+            remove: current
+                .iter()
+                .filter(|r| managed.contains(r) && !wanted.contains(r))
+                .copied()
+                .collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.add.is_empty() && self.remove.is_empty()
+    }
+
+    /// The complete role list of the member after the diff is applied.
+    pub fn apply(&self, current: &[RoleId]) -> Vec<RoleId> {
+        current
+            .iter()
+            .filter(|r| !self.remove.contains(r))
+            .chain(self.add.iter())
+            .copied()
+            .collect()
+    }
+
+    pub fn add_field(&self, embed: &mut CreateEmbed) {
+        if self.is_empty() {
+            embed.field("Role changes", "None, the roles already match the new answers", false);
+            return;
+        }
+        embed.field(
+            "Role changes",
+            format!("Add: {}\nRemove: {}", mention_roles(&self.add), mention_roles(&self.remove)),
+            false,
+        );
+    }
+}
+
+/// The review buttons of an update request. Apply stays disabled while the age bracket is unresolved.
+pub fn update_components(c: &mut CreateComponents, approval_blocked: bool) -> &mut CreateComponents {
+    c.create_action_row(|a| {
+        a.create_button(|b| {
+            b.label("Apply update");
+            b.style(ButtonStyle::Success);
+            b.custom_id("approve_update");
+            b.disabled(approval_blocked);
+            b
+        });
+        a.create_button(|b| {
+            b.label("Reject update");
+            b.style(ButtonStyle::Danger);
+            b.custom_id("reject_update");
+            b
+        });
+        a.create_button(|b| {
+            b.label("Add note");
+            b.style(ButtonStyle::Secondary);
+            b.custom_id("add_note");
+            b
+        })
+    });
+
+    if approval_blocked {
+        crate::validation::bracket_row(c);
+    }
+
+    c
+}
+
+impl Bot {
+    pub async fn reverify_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        let mut text = "Fill out the verification form again with your new answers. \
+            A moderator will review the change and your roles are updated once it is approved."
+            .to_string();
+        match &self.form_url {
+            Some(url) => text.push_str(&format!("\n\nYou can find the form here: {}", url)),
+            None => text.push_str("\n\nAsk a moderator for the link to the form."),
+        }
+
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.embed(|e| {
+                        e.title("Update your answers");
+                        e.description(&text);
+                        e.color(Color::BLURPLE);
+                        e
+                    })
+                })
+            })
+            .await;
+    }
+
+    pub async fn update_request_button(&self, ctx: &Context, msgc: &MessageComponentInteraction) {
+        let _ = msgc
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::DeferredChannelMessageWithSource);
+                f.interaction_response_data(|f| f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL))
+            })
+            .await;
+
+        let approve = msgc.data.custom_id == "approve_update";
+        let result = match self.pending_update(msgc.message.id.0 as i64).await {
+            Ok(frm) if approve => self.apply_update(ctx, msgc, &frm).await,
+            Ok(_) => {
+                self.record_decision(ctx, msgc.message.id.0 as i64, SubmissionStatus::Rejected, msgc.user.id).await;
+                Ok("Update request rejected, the roles stay as they are".to_string())
+            }
+            Err(why) => Err(why),
+        };
+
+        let description = match &result {
+            Ok(text) => text.clone(),
+            Err(why) => why.to_string(),
+        };
+        let _ = msgc
+            .edit_original_interaction_response(ctx, |f| {
+                f.embed(|e| {
+                    e.title(if result.is_ok() { "Done" } else { "Error" });
+                    e.description(description);
+                    e.color(if result.is_ok() { Color::DARK_GREEN } else { Color::DARK_RED });
+                    e
+                })
+            })
+            .await;
+
+        if result.is_err() {
+            return;
+        }
+
+        let _ = msgc
+            .message
+            .clone()
+            .edit(ctx, |m| {
+                m.components(|c| {
+                    c.create_action_row(|a| {
+                        a.create_button(|b| {
+                            b.label(if approve { "Update applied" } else { "Update rejected" });
+                            b.style(if approve { ButtonStyle::Success } else { ButtonStyle::Danger });
+                            b.custom_id("update_decided");
+                            b.disabled(true);
+                            b
+                        });
+                        a.create_button(|b| {
+                            b.label(format!("Action performed by {}", msgc.user.tag()));
+                            b.style(ButtonStyle::Secondary);
+                            b.custom_id("moderator_action");
+                            b.disabled(true);
+                            b.emoji(ReactionType::Custom {
+                                animated: false,
+                                id: EmojiId(900453862702469150),
+                                name: Some("LogoModSystem".to_string()),
+                            });
+                            b
+                        })
+                    })
+                })
+            })
+            .await;
+    }
+
+    async fn pending_update(&self, message_id: i64) -> Result<FormAnswersDB, crate::Error> {
+        let frm = sqlx::query_as!(FormAnswersDB, "SELECT * FROM formanswers WHERE message_id = ?", message_id)
+            .fetch_optional(&self.database)
+            .await?
+            .ok_or("Could not find message in database")?;
+
+        if frm.kind != "update" {
+            return Err("This submission is not an update request".into());
+        }
+        if frm.status != SubmissionStatus::Pending.as_str() {
+            return Err(format!("This update request was already {}", frm.status).into());
+        }
+        if let Some(warnings) = &frm.validation_warnings {
+            return Err(format!("Resolve the age bracket before approving:\n{}", warnings).into());
+        }
+
+        Ok(frm)
+    }
+
+    /// Sets the member's whole role list in one request, so they never end up with half of the change.
+    #[tracing::instrument(skip_all, fields(message_id = frm.message_id, user_id = frm.user_id, moderator = msgc.user.id.0))]
+    async fn apply_update(
+        &self,
+        ctx: &Context,
+        msgc: &MessageComponentInteraction,
+        frm: &FormAnswersDB,
+    ) -> Result<String, crate::Error> {
+        let guild_id = msgc.guild_id.ok_or("Update requests can only be handled on the server")?;
+        let member = guild_id.member(ctx, frm.user_id as u64).await?;
+
+        // the member's roles may have changed since the request was posted
+        let wanted = self.roles.for_answers(
+            frm.is_female,
+            frm.is_18_plus,
+            frm.is_30_plus,
+            frm.diagnosis_status.as_deref().unwrap_or_default(),
+        );
+        let diff = RoleDiff::new(&member.roles, &wanted, &self.roles.managed());
+        if !diff.is_empty() {
+            let roles = diff.apply(&member.roles);
+            crate::metrics::discord("edit_member", guild_id.edit_member(ctx, member.user.id, |m| m.roles(&roles)))
+                .await
+                .inspect_err(|_| crate::metrics::ROLE_ASSIGNMENT_ERRORS.inc())?;
+        }
+        let gid = guild_id.0 as i64;
+        crate::revoke::record_removed_roles(&self.database, gid, frm.user_id, &diff.remove).await?;
+        crate::revoke::record_granted_roles(&self.database, frm.message_id, gid, frm.user_id, &diff.add).await?;
+
+        self.record_decision(ctx, frm.message_id, SubmissionStatus::Approved, msgc.user.id).await;
+        self.track_age(guild_id, frm).await;
+
+        let _ = member
+            .user
+            .direct_message(ctx, |m| m.content("Your updated verification answers were approved and your roles were updated."))
+            .await;
+
+        Ok(format!(
+            "Update applied\nAdded: {}\nRemoved: {}",
+            mention_roles(&diff.add),
+            mention_roles(&diff.remove)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RoleDiff;
+    use serenity::model::id::RoleId;
+
+    #[test]
+    fn only_managed_roles_are_removed() {
+        let managed = [RoleId(1), RoleId(2), RoleId(3)];
+        let current = [RoleId(1), RoleId(2), RoleId(99)];
+        let wanted = [RoleId(1), RoleId(3)];
+
+        let diff = RoleDiff::new(&current, &wanted, &managed);
+        assert_eq!(diff.add, vec![RoleId(3)]);
+        assert_eq!(diff.remove, vec![RoleId(2)]);
+        assert_eq!(diff.apply(&current), vec![RoleId(1), RoleId(99), RoleId(3)]);
+    }
+
+    #[test]
+    fn matching_roles_give_an_empty_diff() {
+        let roles = [RoleId(1), RoleId(2)];
+        assert!(RoleDiff::new(&roles, &roles, &roles).is_empty());
+    }
+}
@@ -0,0 +1,310 @@
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::{
+        id::{GuildId, MessageId, RoleId},
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction,
+                ApplicationCommandInteractionDataOptionValue as OptionValue,
+            },
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        prelude::User,
+        Timestamp,
+    },
+    utils::Color,
+};
+use sqlx::SqlitePool;
+
+use crate::bot::{Bot, FormAnswersDB};
+use crate::embeds::mention_roles;
+use crate::structs::SubmissionStatus;
+
+/// Remembers which roles a submission handed out.
+pub async fn record_granted_roles(
+    pool: &SqlitePool,
+    message_id: i64,
+    guild_id: i64,
+    user_id: i64,
+    roles: &[RoleId],
+) -> Result<(), sqlx::Error> {
+    let granted_at = Timestamp::now().unix_timestamp();
+    for role in roles.iter() {
+        let role_id = role.0 as i64;
+        sqlx::query!(
+            "INSERT INTO granted_roles (message_id, guild_id, user_id, role_id, granted_at) VALUES (?, ?, ?, ?, ?)",
+            message_id,
+            guild_id,
+            user_id,
+            role_id,
+            granted_at
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Marks granted roles as taken away again.
+pub async fn record_removed_roles(
+    pool: &SqlitePool,
+    guild_id: i64,
+    user_id: i64,
+    roles: &[RoleId],
+) -> Result<(), sqlx::Error> {
+    let removed_at = Timestamp::now().unix_timestamp();
+    for role in roles.iter() {
+        let role_id = role.0 as i64;
+        sqlx::query!(
+            "UPDATE granted_roles SET removed_at = ? WHERE guild_id = ? AND user_id = ? AND role_id = ? AND removed_at IS NULL",
+            removed_at,
+            guild_id,
+            user_id,
+            role_id
+        )
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
+}
+
+impl Bot {
+    pub async fn revoke_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        if !crate::commands::is_moderator(cmd) {
+            crate::commands::respond_error(ctx, cmd, "Only moderators can revoke verifications.").await;
+            return;
+        }
+        let guild_id = match cmd.guild_id {
+            Some(g) => g,
+            None => {
+                crate::commands::respond_error(ctx, cmd, "This command can only be used on the server.").await;
+                return;
+            }
+        };
+
+        let mut user = None;
+        let mut reason = None;
+        let mut action = None;
+        for option in cmd.data.options.iter() {
+            match (option.name.as_str(), option.resolved.as_ref()) {
+                ("user", Some(OptionValue::User(u, _))) => user = Some(u.clone()),
+                ("reason", Some(OptionValue::String(s))) => reason = Some(s.clone()),
+                ("action", Some(OptionValue::String(s))) => action = Some(s.clone()),
+                _ => {}
+            }
+        }
+        let user = match user {
+            Some(u) => u,
+            None => {
+                crate::commands::respond_error(ctx, cmd, "Pick the member to revoke.").await;
+                return;
+            }
+        };
+
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::DeferredChannelMessageWithSource);
+                f.interaction_response_data(|f| f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL))
+            })
+            .await;
+
+        let result = self
+            .revoke(ctx, guild_id, &user, cmd, reason.as_deref(), action.as_deref())
+            .await;
+
+        let _ = cmd
+            .edit_original_interaction_response(ctx, |f| {
+                f.embed(|e| {
+                    match &result {
+                        Ok(summary) => {
+                            e.title("Verification revoked");
+                            e.description(summary);
+                            e.color(Color::DARK_GREEN);
+                        }
+                        Err(why) => {
+                            e.title("Error");
+                            e.description(why);
+                            e.color(Color::DARK_RED);
+                        }
+                    }
+                    e
+                })
+            })
+            .await;
+    }
+
+    #[tracing::instrument(skip_all, fields(guild_id = guild_id.0, user_id = user.id.0, moderator = cmd.user.id.0))]
+    async fn revoke(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        user: &User,
+        cmd: &ApplicationCommandInteraction,
+        reason: Option<&str>,
+        action: Option<&str>,
+    ) -> Result<String, crate::Error> {
+        let gid = guild_id.0 as i64;
+        let uid = user.id.0 as i64;
+        let moderator_id = cmd.user.id.0 as i64;
+
+        let approved = sqlx::query_as!(
+            FormAnswersDB,
+            "SELECT * FROM formanswers WHERE user_id = ?1 AND (guild_id = ?2 OR guild_id IS NULL) AND status IN ('approved', 'approved_manually')
+            ORDER BY decided_at DESC",
+            uid,
+            gid
+        )
+        .fetch_all(&self.database)
+        .await?;
+        let latest = approved
+            .first()
+            .ok_or_else(|| format!("{} has no approved verification to revoke", user.tag()))?;
+
+        let tracked = sqlx::query!(
+            "SELECT DISTINCT role_id FROM granted_roles WHERE guild_id = ? AND user_id = ? AND removed_at IS NULL",
+            gid,
+            uid
+        )
+        .fetch_all(&self.database)
+        .await?;
+        // submissions approved before roles were tracked fall back to what their answers map to
+        let granted: Vec<RoleId> = if tracked.is_empty() {
+            self.roles.for_answers(
+                latest.is_female,
+                latest.is_18_plus,
+                latest.is_30_plus,
+                latest.diagnosis_status.as_deref().unwrap_or_default(),
+            )
+        } else {
+            tracked.iter().map(|r| RoleId(r.role_id as u64)).collect()
+        };
+
+        let member = guild_id.member(ctx, user.id).await.ok();
+        let mut removed = Vec::new();
+        if let Some(member) = &member {
+            removed = granted.iter().filter(|r| member.roles.contains(r)).copied().collect();
+            if !removed.is_empty() {
+                let roles: Vec<RoleId> = member.roles.iter().filter(|r| !removed.contains(r)).copied().collect();
+                guild_id.edit_member(ctx, user.id, |m| m.roles(&roles)).await?;
+            }
+        }
+        record_removed_roles(&self.database, gid, uid, &granted).await?;
+
+        // the approval keeps its moderator and time
+        let revoked_at = Timestamp::now().unix_timestamp();
+        let revoked = SubmissionStatus::Revoked.as_str();
+        sqlx::query!(
+            "UPDATE formanswers SET status = ?, revoked_by = ?, revoked_at = ?
+            WHERE user_id = ? AND (guild_id = ? OR guild_id IS NULL) AND status IN ('approved', 'approved_manually')",
+            revoked,
+            moderator_id,
+            revoked_at,
+            uid,
+            gid
+        )
+        .execute(&self.database)
+        .await?;
+        sqlx::query!("DELETE FROM age_tracking WHERE guild_id = ? AND user_id = ?", gid, uid)
+            .execute(&self.database)
+            .await?;
+
+        let reason_text = reason.unwrap_or("No reason given");
+        let mut summary = format!(
+            "{} (<@{}>)\nRemoved roles: {}\nReason: {}",
+            user.tag(),
+            uid,
+            mention_roles(&removed),
+            reason_text
+        );
+        if member.is_none() {
+            summary.push_str("\nThe member is no longer on the server, only the records were updated.");
+        }
+        crate::audit::record(
+            &self.database,
+            gid,
+            uid,
+            Some(moderator_id),
+            "revoke",
+            Some(&format!("Removed roles: {}. Reason: {}", mention_roles(&removed), reason_text)),
+        )
+        .await;
+
+        // the verification is revoked already, a failed kick or ban is reported instead of aborting
+        let audit_reason = format!("Verification revoked by {}: {}", cmd.user.tag(), reason_text);
+        match (action, &member) {
+            (Some("kick"), Some(member)) => {
+                match member.kick_with_reason(ctx, &audit_reason).await {
+                    Ok(_) => {
+                        self.record_fingerprint(member, latest, SubmissionStatus::Kicked).await;
+                        crate::audit::record(&self.database, gid, uid, Some(moderator_id), "kick", Some(reason_text)).await;
+                        summary.push_str("\nThe member was kicked.");
+                    }
+                    Err(why) => summary.push_str(&format!("\nKick failed: {}", why)),
+                }
+            }
+            (Some("ban"), Some(member)) => {
+                match member.ban_with_reason(ctx, 0, &audit_reason).await {
+                    Ok(_) => {
+                        self.record_fingerprint(member, latest, SubmissionStatus::Banned).await;
+                        crate::audit::record(&self.database, gid, uid, Some(moderator_id), "ban", Some(reason_text)).await;
+                        summary.push_str("\nThe member was banned.");
+                    }
+                    Err(why) => summary.push_str(&format!("\nBan failed: {}", why)),
+                }
+            }
+            (Some("ban"), None) => match guild_id.ban_with_reason(ctx, user.id, 0, &audit_reason).await {
+                Ok(_) => {
+                    crate::audit::record(&self.database, gid, uid, Some(moderator_id), "ban", Some(reason_text)).await;
+                    summary.push_str("\nThe user was banned.");
+                }
+                Err(why) => summary.push_str(&format!("\nBan failed: {}", why)),
+            },
+            _ => {}
+        }
+
+        self.close_review_thread(
+            ctx,
+            latest.message_id,
+            &format!("Verification revoked by <@{}>\nReason: {}", moderator_id, reason_text),
+        )
+        .await;
+
+        // note the revocation on the original review message
+        if let Ok(message) = self
+            .responses_channel
+            .message(ctx, MessageId(latest.message_id as u64))
+            .await
+        {
+            if let Some(embed) = message.embeds.first() {
+                let mut embed = CreateEmbed::from(embed.clone());
+                embed.field(
+                    "Verification revoked",
+                    format!("By {} on <t:{}:f>\nReason: {}", cmd.user.tag(), revoked_at, reason_text),
+                    false,
+                );
+                embed.color(Color::DARK_RED);
+                crate::embeds::fit(&mut embed);
+                let _ = self
+                    .responses_channel
+                    .edit_message(ctx, message.id, |m| m.set_embed(embed))
+                    .await;
+            }
+        }
+
+        let _ = self
+            .responses_channel
+            .send_message(ctx, |f| {
+                f.embed(|e| {
+                    e.title("Verification revoked");
+                    e.description(&summary);
+                    e.field("Moderator", cmd.user.tag(), true);
+                    e.color(Color::DARK_RED);
+                    e
+                })
+            })
+            .await;
+
+        Ok(summary)
+    }
+}
@@ -0,0 +1,104 @@
+use serenity::{builder::CreateEmbed, model::guild::Member, model::Timestamp, utils::Color};
+
+use crate::bot::Bot;
+use crate::structs::RiskSettings;
+
+/// Signals that help moderators spot throwaway accounts.
+pub struct RiskReport {
+    pub account_created: i64,
+    pub joined_at: Option<i64>,
+    pub has_avatar: bool,
+    pub prior_submissions: i64,
+    pub prior_rejections: i64,
+    pub score: i64,
+}
+
+impl RiskReport {
+    pub fn color(&self, settings: &RiskSettings) -> Color {
+        if self.score >= settings.danger_threshold {
+            Color::RED
+        } else if self.score >= settings.warn_threshold {
+            Color::ORANGE
+        } else {
+            Color::BLURPLE
+        }
+    }
+
+    pub fn add_fields(&self, embed: &mut CreateEmbed, settings: &RiskSettings) {
+        let level = if self.score >= settings.danger_threshold {
+            "high"
+        } else if self.score >= settings.warn_threshold {
+            "elevated"
+        } else {
+            "low"
+        };
+
+        embed.field(
+            "Account created",
+            format!("<t:{0}:D> (<t:{0}:R>)", self.account_created),
+            true,
+        );
+        embed.field(
+            "Joined server",
+            match self.joined_at {
+                Some(ts) => format!("<t:{0}:D> (<t:{0}:R>)", ts),
+                None => "Unknown".to_string(),
+            },
+            true,
+        );
+        embed.field("Avatar", if self.has_avatar { "Yes" } else { "No" }, true);
+        embed.field(
+            "Prior submissions",
+            format!("{} ({} kicked or banned)", self.prior_submissions, self.prior_rejections),
+            true,
+        );
+        embed.field("Risk score", format!("{} ({})", self.score, level), true);
+    }
+}
+
+impl Bot {
+    pub async fn assess_risk(&self, member: &Member) -> RiskReport {
+        let settings = &self.risk;
+        let uid = member.user.id.0 as i64;
+
+        let history = crate::metrics::db(
+            "submission_history",
+            sqlx::query!(
+                r#"SELECT COUNT(*) AS "submissions!: i64", COALESCE(SUM(status IN ('kicked', 'banned')), 0) AS "rejections!: i64"
+                FROM formanswers WHERE user_id = ?"#,
+                uid
+            )
+            .fetch_one(&self.database),
+        )
+        .await;
+        let (prior_submissions, prior_rejections) = match history {
+            Ok(h) => (h.submissions, h.rejections),
+            Err(why) => {
+                tracing::error!("Could not look up history of {}: {:?}", member.user.tag(), why);
+                (0, 0)
+            }
+        };
+
+        let account_created = member.user.id.created_at().unix_timestamp();
+        let account_age_days = (Timestamp::now().unix_timestamp() - account_created) / (24 * 60 * 60);
+        let has_avatar = member.user.avatar.is_some();
+
+        let mut score = prior_submissions * settings.weight_prior_submission
+            + prior_rejections * settings.weight_prior_rejection;
+        if account_age_days < settings.new_account_days {
+            score += settings.weight_new_account;
+        }
+        if !has_avatar {
+            score += settings.weight_no_avatar;
+        }
+
+        RiskReport {
+            account_created,
+            joined_at: member.joined_at.map(|ts| ts.unix_timestamp()),
+            has_avatar,
+            prior_submissions,
+            prior_rejections,
+            score,
+        }
+    }
+}
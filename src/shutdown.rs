@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::{watch, OwnedRwLockReadGuard, RwLock};
+use tokio::time::Interval;
+
+/// Held while a handler or job run is doing work, shutdown waits until every guard is dropped.
+pub type Running = OwnedRwLockReadGuard<()>;
+
+/// Lets event handlers and background jobs finish what they started before the bot exits.
+pub struct Coordinator {
+    stop: watch::Sender<bool>,
+    running: Arc<RwLock<()>>,
+}
+
+impl Default for Coordinator {
+    fn default() -> Self {
+        Coordinator {
+            stop: watch::channel(false).0,
+            running: Arc::new(RwLock::new(())),
+        }
+    }
+}
+
+impl Coordinator {
+    /// Marks work as started, `None` once shutdown has begun and nothing new should be picked up.
+    pub fn enter(&self) -> Option<Running> {
+        if *self.stop.borrow() {
+            return None;
+        }
+        self.running.clone().try_read_owned().ok()
+    }
+
+    /// Waits for the next run of a background job, `None` means the job should exit instead.
+    pub async fn tick(&self, interval: &mut Interval) -> Option<Running> {
+        let mut stop = self.stop.subscribe();
+        if *stop.borrow() {
+            return None;
+        }
+        tokio::select! {
+            _ = interval.tick() => self.enter(),
+            _ = stop.changed() => None,
+        }
+    }
+
+    /// Stops new work and waits up to `timeout` for running work to finish.
+    /// Returns false if work was still running when the timeout passed.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        let _ = self.stop.send(true);
+        // the write lock is only granted once every guard is gone
+        tokio::time::timeout(timeout, self.running.write()).await.is_ok()
+    }
+}
+
+/// Resolves on ctrl+c, or SIGTERM where there is such a thing.
+pub async fn signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate()).expect("Could not register SIGTERM handler");
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.expect("Could not register ctrl+c handler"),
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.expect("Could not register ctrl+c handler");
+}
@@ -0,0 +1,308 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::{
+    client::Context,
+    model::{
+        id::{ChannelId, GuildId, RoleId},
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction,
+                ApplicationCommandInteractionDataOptionValue as OptionValue,
+            },
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        Timestamp,
+    },
+    utils::Color,
+};
+use sqlx::SqlitePool;
+
+use crate::bot::Bot;
+use crate::shutdown::Coordinator;
+use crate::structs::SlaSettings;
+
+const SWEEP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Longer digests are cut off, the rest is only counted.
+const MAX_DIGEST_LINES: usize = 20;
+
+const LEVEL_ALERT: i64 = 1;
+const LEVEL_ESCALATE: i64 = 2;
+
+struct Overdue {
+    message_id: i64,
+    user_id: i64,
+    submitted_at: i64,
+    level: i64,
+}
+
+/// The limits of a guild, with the defaults filled in where it has no override.
+async fn guild_settings(pool: &SqlitePool, defaults: SlaSettings, guild_id: i64) -> Result<SlaSettings, sqlx::Error> {
+    let row = sqlx::query!(
+        "SELECT alert_after_hours, alert_role_id, escalate_after_hours, escalate_role_id FROM sla_settings WHERE guild_id = ?",
+        guild_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match row {
+        Some(r) => SlaSettings {
+            alert_after_hours: r.alert_after_hours.unwrap_or(defaults.alert_after_hours),
+            alert_role: r.alert_role_id.map(|id| RoleId(id as u64)).or(defaults.alert_role),
+            escalate_after_hours: r.escalate_after_hours.unwrap_or(defaults.escalate_after_hours),
+            escalate_role: r.escalate_role_id.map(|id| RoleId(id as u64)).or(defaults.escalate_role),
+        },
+        None => defaults,
+    })
+}
+
+fn describe_limit(hours: i64, role: Option<RoleId>) -> String {
+    if hours <= 0 {
+        return "Disabled".to_string();
+    }
+    match role {
+        Some(role) => format!("After {}h, pings <@&{}>", hours, role.0),
+        None => format!("After {}h, no role pinged", hours),
+    }
+}
+
+impl Bot {
+    pub fn sla_job(&self) -> SlaJob {
+        SlaJob {
+            database: self.database.clone(),
+            shutdown: self.shutdown.clone(),
+            defaults: self.sla,
+            channel: self.responses_channel,
+        }
+    }
+
+    pub async fn sla_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        if !crate::commands::is_moderator(cmd) {
+            crate::commands::respond_error(ctx, cmd, "Only moderators can change the review time limits.").await;
+            return;
+        }
+        let guild_id = match cmd.guild_id {
+            Some(g) => g.0 as i64,
+            None => {
+                crate::commands::respond_error(ctx, cmd, "This command can only be used on the server.").await;
+                return;
+            }
+        };
+
+        let mut alert_hours = None;
+        let mut alert_role = None;
+        let mut escalate_hours = None;
+        let mut escalate_role = None;
+        for option in cmd.data.options.iter() {
+            match (option.name.as_str(), option.resolved.as_ref()) {
+                ("alert_hours", Some(OptionValue::Integer(h))) => alert_hours = Some(*h),
+                ("alert_role", Some(OptionValue::Role(r))) => alert_role = Some(r.id.0 as i64),
+                ("escalate_hours", Some(OptionValue::Integer(h))) => escalate_hours = Some(*h),
+                ("escalate_role", Some(OptionValue::Role(r))) => escalate_role = Some(r.id.0 as i64),
+                _ => {}
+            }
+        }
+
+        let changed = !cmd.data.options.is_empty();
+        if changed {
+            if let Err(why) = sqlx::query!(
+                "INSERT INTO sla_settings (guild_id, alert_after_hours, alert_role_id, escalate_after_hours, escalate_role_id)
+                VALUES (?, ?, ?, ?, ?)
+                ON CONFLICT (guild_id) DO UPDATE SET
+                    alert_after_hours = COALESCE(excluded.alert_after_hours, alert_after_hours),
+                    alert_role_id = COALESCE(excluded.alert_role_id, alert_role_id),
+                    escalate_after_hours = COALESCE(excluded.escalate_after_hours, escalate_after_hours),
+                    escalate_role_id = COALESCE(excluded.escalate_role_id, escalate_role_id)",
+                guild_id,
+                alert_hours,
+                alert_role,
+                escalate_hours,
+                escalate_role
+            )
+            .execute(&self.database)
+            .await
+            {
+                tracing::error!("Could not save review time limits of {}: {:?}", guild_id, why);
+                crate::commands::respond_error(ctx, cmd, "Could not save the review time limits.").await;
+                return;
+            }
+        }
+
+        let settings = match guild_settings(&self.database, self.sla, guild_id).await {
+            Ok(s) => s,
+            Err(why) => {
+                tracing::error!("Could not read review time limits of {}: {:?}", guild_id, why);
+                crate::commands::respond_error(ctx, cmd, "Could not read the review time limits.").await;
+                return;
+            }
+        };
+
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.embed(|e| {
+                        e.title(if changed { "Review time limits updated" } else { "Review time limits" });
+                        e.field("Alert", describe_limit(settings.alert_after_hours, settings.alert_role), false);
+                        e.field(
+                            "Escalation",
+                            describe_limit(settings.escalate_after_hours, settings.escalate_role),
+                            false,
+                        );
+                        e.color(Color::BLURPLE);
+                        e
+                    })
+                })
+            })
+            .await;
+    }
+}
+
+/// Reminds moderators of submissions that have been waiting for a decision too long.
+pub struct SlaJob {
+    database: SqlitePool,
+    shutdown: Arc<Coordinator>,
+    defaults: SlaSettings,
+    channel: ChannelId,
+}
+
+impl SlaJob {
+    pub async fn run(self, ctx: Context) {
+        let mut interval = tokio::time::interval(SWEEP_INTERVAL);
+        while let Some(_running) = self.shutdown.tick(&mut interval).await {
+            if let Err(why) = self.sweep(&ctx).await {
+                tracing::error!("Could not check review time limits: {:?}", why);
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "sla_sweep", skip_all)]
+    async fn sweep(&self, ctx: &Context) -> Result<(), sqlx::Error> {
+        // decided submissions don't need to be remembered anymore
+        sqlx::query!("DELETE FROM sla_alerts WHERE message_id NOT IN (SELECT message_id FROM formanswers WHERE status = 'pending')")
+            .execute(&self.database)
+            .await?;
+
+        let guilds = sqlx::query!(
+            r#"SELECT DISTINCT guild_id AS "guild_id!: i64" FROM formanswers WHERE status = 'pending' AND guild_id IS NOT NULL"#
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        for guild in guilds.iter() {
+            let settings = guild_settings(&self.database, self.defaults, guild.guild_id).await?;
+            self.check_guild(ctx, GuildId(guild.guild_id as u64), settings).await?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(guild_id = guild_id.0))]
+    async fn check_guild(&self, ctx: &Context, guild_id: GuildId, settings: SlaSettings) -> Result<(), sqlx::Error> {
+        let gid = guild_id.0 as i64;
+        let pending = sqlx::query_as!(
+            Overdue,
+            r#"SELECT f.message_id, f.user_id, f.submitted_at AS "submitted_at!: i64", COALESCE((SELECT a.level FROM sla_alerts a WHERE a.message_id = f.message_id), 0) AS "level!: i64"
+            FROM formanswers f
+            WHERE f.status = 'pending' AND f.guild_id = ? AND f.submitted_at IS NOT NULL
+            ORDER BY f.submitted_at"#,
+            gid
+        )
+        .fetch_all(&self.database)
+        .await?;
+
+        let now = Timestamp::now().unix_timestamp();
+        let passed = |hours: i64, submitted_at: i64| hours > 0 && now - submitted_at >= hours * 60 * 60;
+
+        // a submission that is already past the escalation limit skips the plain alert
+        let mut escalate = Vec::new();
+        let mut alert = Vec::new();
+        for submission in pending.iter() {
+            if passed(settings.escalate_after_hours, submission.submitted_at) {
+                if submission.level < LEVEL_ESCALATE {
+                    escalate.push(submission);
+                }
+            } else if passed(settings.alert_after_hours, submission.submitted_at) && submission.level < LEVEL_ALERT {
+                alert.push(submission);
+            }
+        }
+
+        for (level, overdue, hours, role) in [
+            (LEVEL_ESCALATE, &escalate, settings.escalate_after_hours, settings.escalate_role),
+            (LEVEL_ALERT, &alert, settings.alert_after_hours, settings.alert_role),
+        ] {
+            if overdue.is_empty() {
+                continue;
+            }
+            if !self.post_digest(ctx, guild_id, level, overdue, hours, role).await {
+                continue;
+            }
+            for submission in overdue.iter() {
+                sqlx::query!(
+                    "INSERT OR REPLACE INTO sla_alerts (message_id, level, alerted_at) VALUES (?, ?, ?)",
+                    submission.message_id,
+                    level,
+                    now
+                )
+                .execute(&self.database)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn post_digest(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        level: i64,
+        overdue: &[&Overdue],
+        hours: i64,
+        role: Option<RoleId>,
+    ) -> bool {
+        let mut lines: Vec<String> = overdue
+            .iter()
+            .take(MAX_DIGEST_LINES)
+            .map(|s| {
+                format!(
+                    "<@{}>, submitted <t:{}:R> ([review message](https://discord.com/channels/{}/{}/{}))",
+                    s.user_id, s.submitted_at, guild_id.0, self.channel.0, s.message_id
+                )
+            })
+            .collect();
+        if overdue.len() > MAX_DIGEST_LINES {
+            lines.push(format!("... and {} more", overdue.len() - MAX_DIGEST_LINES));
+        }
+
+        let escalated = level == LEVEL_ESCALATE;
+        let result = self
+            .channel
+            .send_message(ctx, |f| {
+                if let Some(role) = role {
+                    f.content(format!("<@&{}>", role.0));
+                    f.allowed_mentions(|m| m.roles(vec![role]));
+                }
+                f.embed(|e| {
+                    e.title(if escalated { "Submissions overdue for review" } else { "Submissions waiting for review" });
+                    e.description(format!(
+                        "{} submission(s) have been pending for more than {} hours:\n\n{}",
+                        overdue.len(),
+                        hours,
+                        lines.join("\n")
+                    ));
+                    e.color(if escalated { Color::RED } else { Color::ORANGE });
+                    e
+                })
+            })
+            .await;
+
+        match result {
+            Ok(_) => true,
+            Err(why) => {
+                tracing::error!("Could not post review reminder for {}: {:?}", guild_id, why);
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,346 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::{
+        id::ChannelId,
+        interactions::{
+            application_command::{
+                ApplicationCommandInteraction,
+                ApplicationCommandInteractionDataOptionValue as OptionValue,
+            },
+            InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+        },
+        Timestamp,
+    },
+    utils::Color,
+};
+use sqlx::SqlitePool;
+
+use crate::bot::Bot;
+use crate::shutdown::Coordinator;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const WEEK: i64 = 7 * 24 * 60 * 60;
+const REPORT_NAME: &str = "weekly_stats";
+/// Moderators listed by name, the least active of the rest are only counted.
+const MAX_LISTED_MODERATORS: usize = 10;
+
+/// Numbers about submissions made since a point in time.
+pub struct Stats {
+    pub since: Option<i64>,
+    pub statuses: Vec<(String, i64)>,
+    pub median_decision_secs: Option<i64>,
+    pub moderators: Vec<(i64, String, i64)>,
+    pub diagnoses: Vec<(String, i64)>,
+    pub brackets: Vec<(&'static str, i64)>,
+}
+
+/// Turns seconds into something like `2d 4h` or `35m`.
+pub fn format_duration(secs: i64) -> String {
+    let days = secs / (24 * 60 * 60);
+    let hours = secs % (24 * 60 * 60) / (60 * 60);
+    let minutes = secs % (60 * 60) / 60;
+    if days > 0 {
+        format!("{}d {}h", days, hours)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+/// Median of sorted values, the mean of the two middle ones for an even count.
+fn median(sorted: &[i64]) -> Option<i64> {
+    match sorted.len() {
+        0 => None,
+        n if n % 2 == 0 => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2),
+        n => Some(sorted[n / 2]),
+    }
+}
+
+fn lines<T: std::fmt::Display>(rows: &[(T, i64)]) -> String {
+    if rows.is_empty() {
+        return "None".to_string();
+    }
+    rows.iter().map(|(name, count)| format!("{}: {}", name, count)).collect::<Vec<_>>().join("\n")
+}
+
+/// One line per moderator with their actions, the most active first.
+fn moderator_lines(rows: &[(i64, String, i64)]) -> String {
+    let mut moderators: Vec<(i64, i64, Vec<String>)> = Vec::new();
+    for (id, status, count) in rows.iter() {
+        let entry = format!("{} {}", count, status);
+        match moderators.iter_mut().find(|(m, _, _)| m == id) {
+            Some((_, total, entries)) => {
+                *total += count;
+                entries.push(entry);
+            }
+            None => moderators.push((*id, *count, vec![entry])),
+        }
+    }
+    if moderators.is_empty() {
+        return "None".to_string();
+    }
+    moderators.sort_by_key(|m| std::cmp::Reverse(m.1));
+
+    let mut lines: Vec<String> = moderators
+        .iter()
+        .take(MAX_LISTED_MODERATORS)
+        .map(|(id, _, entries)| format!("<@{}>: {}", id, entries.join(", ")))
+        .collect();
+    if moderators.len() > MAX_LISTED_MODERATORS {
+        lines.push(format!("…and {} more", moderators.len() - MAX_LISTED_MODERATORS));
+    }
+    crate::embeds::truncate(&lines.join("\n"), crate::embeds::MAX_FIELD_VALUE)
+}
+
+pub async fn compute(pool: &SqlitePool, since: Option<i64>) -> Result<Stats, sqlx::Error> {
+    let statuses = sqlx::query!(
+        r#"SELECT status, COUNT(*) AS "count!: i64" FROM formanswers
+        WHERE ?1 IS NULL OR submitted_at >= ?1
+        GROUP BY status ORDER BY 2 DESC"#,
+        since
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| (r.status, r.count))
+    .collect();
+
+    let mut durations: Vec<i64> = sqlx::query!(
+        r#"SELECT decided_at - submitted_at AS "secs!: i64" FROM formanswers
+        WHERE (?1 IS NULL OR submitted_at >= ?1) AND decided_at IS NOT NULL AND submitted_at IS NOT NULL
+          AND status != 'superseded'"#,
+        since
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| r.secs)
+    .collect();
+    durations.sort_unstable();
+    let median_decision_secs = median(&durations);
+
+    // a revoked approval still counts as an approval of whoever approved it
+    let moderators = sqlx::query!(
+        r#"SELECT moderator_id AS "moderator_id!: i64", status AS "status!: String", COUNT(*) AS "count!: i64" FROM (
+            SELECT moderator_id, CASE WHEN status = 'revoked' THEN 'approved' ELSE status END AS status FROM formanswers
+            WHERE (?1 IS NULL OR submitted_at >= ?1) AND moderator_id IS NOT NULL
+            UNION ALL
+            SELECT revoked_by, 'revoked' FROM formanswers
+            WHERE (?1 IS NULL OR submitted_at >= ?1) AND revoked_by IS NOT NULL
+        )
+        GROUP BY moderator_id, status ORDER BY moderator_id, 3 DESC"#,
+        since
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| (r.moderator_id, r.status, r.count))
+    .collect();
+
+    // redacted submissions no longer have answers to count
+    let diagnoses = sqlx::query!(
+        r#"SELECT diagnosis_status AS "diagnosis_status!: String", COUNT(*) AS "count!: i64" FROM formanswers
+        WHERE (?1 IS NULL OR submitted_at >= ?1) AND diagnosis_status IS NOT NULL AND redacted_at IS NULL
+        GROUP BY diagnosis_status ORDER BY 2 DESC"#,
+        since
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| (r.diagnosis_status, r.count))
+    .collect();
+
+    let brackets = sqlx::query!(
+        r#"SELECT COALESCE(SUM(NOT is_18_plus AND NOT is_30_plus), 0) AS "minor!: i64",
+            COALESCE(SUM(is_18_plus AND NOT is_30_plus), 0) AS "adult!: i64",
+            COALESCE(SUM(is_30_plus), 0) AS "senior!: i64"
+        FROM formanswers WHERE (?1 IS NULL OR submitted_at >= ?1) AND redacted_at IS NULL"#,
+        since
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(Stats {
+        since,
+        statuses,
+        median_decision_secs,
+        moderators,
+        diagnoses,
+        brackets: vec![
+            ("Under 18", brackets.minor),
+            ("18 to 29", brackets.adult),
+            ("30+", brackets.senior),
+        ],
+    })
+}
+
+impl Stats {
+    pub fn fill_embed(&self, e: &mut CreateEmbed) {
+        let total: i64 = self.statuses.iter().map(|(_, c)| c).sum();
+        e.description(match self.since {
+            Some(ts) => format!("{} submission(s) since <t:{}:f>", total, ts),
+            None => format!("{} submission(s) in total", total),
+        });
+        e.field("By status", lines(&self.statuses), true);
+        e.field(
+            "Median time to decision",
+            self.median_decision_secs.map(format_duration).unwrap_or_else(|| "No decisions".to_string()),
+            true,
+        );
+
+        e.field("Moderator actions", moderator_lines(&self.moderators), false);
+        e.field("Diagnosis status", lines(&self.diagnoses), true);
+        e.field("Age bracket", lines(&self.brackets), true);
+        e.color(Color::BLURPLE);
+    }
+}
+
+impl Bot {
+    pub async fn stats_command(&self, ctx: &Context, cmd: &ApplicationCommandInteraction) {
+        if !crate::commands::is_moderator(cmd) {
+            crate::commands::respond_error(ctx, cmd, "Only moderators can see statistics.").await;
+            return;
+        }
+
+        let period = match cmd.data.options.iter().find(|o| o.name == "period").and_then(|o| o.resolved.as_ref()) {
+            Some(OptionValue::String(s)) => s.clone(),
+            _ => "week".to_string(),
+        };
+        let days = match period.as_str() {
+            "day" => Some(1),
+            "week" => Some(7),
+            "month" => Some(30),
+            _ => None,
+        };
+        let since = days.map(|d| Timestamp::now().unix_timestamp() - d * 24 * 60 * 60);
+
+        let stats = match compute(&self.database, since).await {
+            Ok(s) => s,
+            Err(why) => {
+                tracing::error!("Could not compute statistics: {:?}", why);
+                crate::commands::respond_error(ctx, cmd, "Could not compute statistics.").await;
+                return;
+            }
+        };
+
+        let _ = cmd
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::ChannelMessageWithSource);
+                f.interaction_response_data(|f| {
+                    f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                    f.embed(|e| {
+                        e.title("Verification statistics");
+                        stats.fill_embed(e);
+                        e
+                    })
+                })
+            })
+            .await;
+    }
+
+    pub fn weekly_report_job(&self) -> Option<WeeklyReportJob> {
+        self.reports.channel.map(|channel| WeeklyReportJob {
+            database: self.database.clone(),
+            shutdown: self.shutdown.clone(),
+            channel,
+        })
+    }
+}
+
+/// Posts the statistics of the past week once a week.
+pub struct WeeklyReportJob {
+    database: SqlitePool,
+    shutdown: Arc<Coordinator>,
+    channel: ChannelId,
+}
+
+impl WeeklyReportJob {
+    pub async fn run(self, ctx: Context) {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        while let Some(_running) = self.shutdown.tick(&mut interval).await {
+            if let Err(why) = self.post_if_due(&ctx).await {
+                tracing::error!("Could not post the weekly report: {:?}", why);
+            }
+        }
+    }
+
+    #[tracing::instrument(name = "weekly_report", skip_all)]
+    async fn post_if_due(&self, ctx: &Context) -> Result<(), sqlx::Error> {
+        let now = Timestamp::now().unix_timestamp();
+        let last = sqlx::query!("SELECT last_run_at FROM report_runs WHERE name = ?", REPORT_NAME)
+            .fetch_optional(&self.database)
+            .await?
+            .map(|r| r.last_run_at);
+        if matches!(last, Some(last) if now - last < WEEK) {
+            return Ok(());
+        }
+
+        let stats = compute(&self.database, Some(now - WEEK)).await?;
+        let posted = self
+            .channel
+            .send_message(ctx, |f| {
+                f.embed(|e| {
+                    e.title("Weekly verification report");
+                    stats.fill_embed(e);
+                    e
+                })
+            })
+            .await;
+        if let Err(why) = posted {
+            tracing::error!("Could not post the weekly report: {:?}", why);
+            return Ok(());
+        }
+
+        sqlx::query!(
+            "INSERT OR REPLACE INTO report_runs (name, last_run_at) VALUES (?, ?)",
+            REPORT_NAME,
+            now
+        )
+        .execute(&self.database)
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(59), "0m");
+        assert_eq!(format_duration(35 * 60), "35m");
+        assert_eq!(format_duration(2 * 60 * 60 + 5 * 60), "2h 5m");
+        assert_eq!(format_duration(2 * 24 * 60 * 60 + 4 * 60 * 60 + 59), "2d 4h");
+    }
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(&[]), None);
+        assert_eq!(median(&[5]), Some(5));
+        assert_eq!(median(&[1, 3, 10]), Some(3));
+        assert_eq!(median(&[1, 3, 10, 20]), Some(6));
+    }
+
+    #[test]
+    fn moderator_lines_are_capped() {
+        let rows: Vec<(i64, String, i64)> = (1..=15).map(|id| (id, "approved".to_string(), id)).collect();
+        let text = moderator_lines(&rows);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), MAX_LISTED_MODERATORS + 1);
+        assert_eq!(lines[0], "<@15>: 15 approved");
+        assert_eq!(lines[MAX_LISTED_MODERATORS], "…and 5 more");
+    }
+
+    #[test]
+    fn moderator_lines_group_statuses() {
+        let rows = vec![(1, "approved".to_string(), 3), (1, "kicked".to_string(), 1), (2, "banned".to_string(), 5)];
+        assert_eq!(moderator_lines(&rows), "<@2>: 5 banned\n<@1>: 3 approved, 1 kicked");
+        assert_eq!(moderator_lines(&[]), "None");
+    }
+}
@@ -1,4 +1,5 @@
 
+#[derive(Clone)]
 pub struct GuildRoleSettings {
     pub boomer:        serenity::model::id::RoleId,
     pub fussvolk:      serenity::model::id::RoleId,
@@ -8,4 +9,242 @@ pub struct GuildRoleSettings {
     pub default_member_role: serenity::model::id::RoleId,
     pub f_adult: serenity::model::id::RoleId,
     pub f_child: serenity::model::id::RoleId,
-}
\ No newline at end of file
+}
+
+impl GuildRoleSettings {
+    /// The roles a member gets for their answers.
+    pub fn for_answers(&self, is_female: bool, is_18_plus: bool, is_30_plus: bool, diagnosis_status: &str) -> Vec<serenity::model::id::RoleId> {
+        let mut roles = vec![self.default_member_role];
+
+        if is_18_plus {
+            roles.push(self.fussvolk);
+        }
+        if is_30_plus {
+            roles.push(self.boomer);
+        }
+        if !is_18_plus && !is_30_plus {
+            roles.push(self.fussvoelkchen);
+        }
+
+        if is_female && !is_18_plus && !is_30_plus {
+            roles.push(self.f_child);
+        }
+        if is_female && (is_18_plus || is_30_plus) {
+            roles.push(self.f_adult);
+        }
+
+        match diagnosis_status {
+            "Family Member or Friend of an Autistic Individual." => roles.push(self.non_asd_role),
+            _ => roles.push(self.asd_role),
+        }
+
+        roles
+    }
+
+    /// Every role the bot hands out based on answers.
+    pub fn managed(&self) -> Vec<serenity::model::id::RoleId> {
+        vec![
+            self.default_member_role,
+            self.boomer,
+            self.fussvolk,
+            self.fussvoelkchen,
+            self.asd_role,
+            self.non_asd_role,
+            self.f_adult,
+            self.f_child,
+        ]
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmissionStatus {
+    Pending,
+    Approved,
+    Kicked,
+    Banned,
+    Superseded,
+    Rejected,
+    Revoked,
+    ApprovedManually,
+}
+
+impl SubmissionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubmissionStatus::Pending => "pending",
+            SubmissionStatus::Approved => "approved",
+            SubmissionStatus::Kicked => "kicked",
+            SubmissionStatus::Banned => "banned",
+            SubmissionStatus::Superseded => "superseded",
+            SubmissionStatus::Rejected => "rejected",
+            SubmissionStatus::Revoked => "revoked",
+            SubmissionStatus::ApprovedManually => "approved_manually",
+        }
+    }
+
+    /// Whether the member holds the verified roles because of this submission.
+    pub fn is_approval(&self) -> bool {
+        matches!(self, SubmissionStatus::Approved | SubmissionStatus::ApprovedManually)
+    }
+}
+
+impl std::str::FromStr for SubmissionStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(SubmissionStatus::Pending),
+            "approved" => Ok(SubmissionStatus::Approved),
+            "kicked" => Ok(SubmissionStatus::Kicked),
+            "banned" => Ok(SubmissionStatus::Banned),
+            "superseded" => Ok(SubmissionStatus::Superseded),
+            "rejected" => Ok(SubmissionStatus::Rejected),
+            "revoked" => Ok(SubmissionStatus::Revoked),
+            "approved_manually" => Ok(SubmissionStatus::ApprovedManually),
+            _ => Err(format!("unknown submission status `{}`", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for SubmissionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+pub struct WelcomeSettings {
+    /// Sent to the applicant after approval. Supports `{user}`, `{server}` and `{roles}`.
+    pub dm_template: String,
+    /// Also post a public welcome here if set.
+    pub channel: Option<serenity::model::id::ChannelId>,
+    /// The public welcome. Supports `{mention}` and `{server}` only.
+    pub channel_template: String,
+}
+
+#[derive(Clone)]
+pub struct GracePeriodSettings {
+    /// DM a reminder this many hours after joining, `0` disables reminders.
+    pub remind_after_hours: i64,
+    /// Kick members without a submission after this many days, `0` disables kicking.
+    pub kick_after_days: i64,
+    /// Members with any of these roles are never reminded or kicked.
+    pub exempt_roles: Vec<serenity::model::id::RoleId>,
+}
+
+/// Weights and thresholds for the risk score shown on review messages.
+#[derive(Clone)]
+pub struct RiskSettings {
+    /// Accounts younger than this many days count as new.
+    pub new_account_days: i64,
+    pub weight_new_account: i64,
+    pub weight_no_avatar: i64,
+    /// Added for every earlier submission of the same user.
+    pub weight_prior_submission: i64,
+    /// Added for every earlier submission that ended in a kick or ban.
+    pub weight_prior_rejection: i64,
+    /// Scores at or above this colour the embed orange.
+    pub warn_threshold: i64,
+    /// Scores at or above this colour the embed red.
+    pub danger_threshold: i64,
+    /// Similarity (0.0 to 1.0) above which a rejected applicant is reported as a possible alt.
+    pub alt_match_threshold: f64,
+}
+
+#[derive(Clone)]
+pub struct AgeTransitionSettings {
+    /// Ask the member by DM before moving them into an older bracket instead of doing it right away.
+    pub require_confirmation: bool,
+}
+
+/// What happens when a member with earlier submissions joins again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejoinPolicy {
+    /// Only post a notice with their history.
+    Notify,
+    /// Give previously approved members their roles back right away.
+    Restore,
+    /// Let a moderator decide whether to restore the roles or ask for a new submission.
+    Review,
+}
+
+impl std::str::FromStr for RejoinPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "notify" => Ok(RejoinPolicy::Notify),
+            "restore" => Ok(RejoinPolicy::Restore),
+            "review" => Ok(RejoinPolicy::Review),
+            _ => Err(format!("unknown rejoin policy `{}`", s)),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RejoinSettings {
+    pub policy: RejoinPolicy,
+    /// Roles are only restored automatically if the member was gone for at most this many days,
+    /// `0` means there is no limit.
+    pub max_absence_days: i64,
+}
+
+pub struct ReportSettings {
+    /// Post the weekly statistics here, no report is posted if unset.
+    pub channel: Option<serenity::model::id::ChannelId>,
+}
+
+/// Defaults for the review time limits, each guild can override them with `/sla`.
+#[derive(Clone, Copy)]
+pub struct SlaSettings {
+    /// Ping the alert role about submissions pending this long, `0` disables alerts.
+    pub alert_after_hours: i64,
+    pub alert_role: Option<serenity::model::id::RoleId>,
+    /// Ping the escalation role about submissions pending this long, `0` disables escalation.
+    pub escalate_after_hours: i64,
+    pub escalate_role: Option<serenity::model::id::RoleId>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GuildRoleSettings;
+    use serenity::model::id::RoleId;
+
+    fn roles() -> GuildRoleSettings {
+        GuildRoleSettings {
+            boomer: RoleId(1),
+            fussvolk: RoleId(2),
+            fussvoelkchen: RoleId(3),
+            asd_role: RoleId(4),
+            non_asd_role: RoleId(5),
+            default_member_role: RoleId(6),
+            f_adult: RoleId(7),
+            f_child: RoleId(8),
+        }
+    }
+
+    #[test]
+    fn minors_get_the_child_roles() {
+        let r = roles();
+        assert_eq!(
+            r.for_answers(true, false, false, "Self Diagnosed"),
+            vec![r.default_member_role, r.fussvoelkchen, r.f_child, r.asd_role]
+        );
+    }
+
+    #[test]
+    fn seniors_keep_the_adult_roles() {
+        let r = roles();
+        assert_eq!(
+            r.for_answers(true, true, true, "Formally diagnosed with ASD (Autism spectrum Disorder)"),
+            vec![r.default_member_role, r.fussvolk, r.boomer, r.f_adult, r.asd_role]
+        );
+    }
+
+    #[test]
+    fn friends_and_family_get_the_non_asd_role() {
+        let r = roles();
+        assert_eq!(
+            r.for_answers(false, true, false, "Family Member or Friend of an Autistic Individual."),
+            vec![r.default_member_role, r.fussvolk, r.non_asd_role]
+        );
+    }
+}
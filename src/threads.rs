@@ -0,0 +1,65 @@
+use serenity::{
+    client::Context,
+    model::{channel::Message, id::ChannelId},
+    utils::Color,
+};
+
+use crate::bot::Bot;
+
+/// Minutes of inactivity before Discord hides a review thread, it comes back as soon as someone writes in it.
+const AUTO_ARCHIVE_MINUTES: u16 = 1440;
+
+impl Bot {
+    /// Starts the moderator discussion on a new review message.
+    pub async fn open_review_thread(&self, ctx: &Context, review: &Message, tag: &str) {
+        let name: String = format!("Review: {}", tag).chars().take(100).collect();
+        let thread = match review
+            .channel_id
+            .create_public_thread(ctx, review.id, |t| t.name(name).auto_archive_duration(AUTO_ARCHIVE_MINUTES))
+            .await
+        {
+            Ok(t) => t,
+            Err(why) => {
+                tracing::error!("Could not create review thread for {}: {:?}", review.id, why);
+                return;
+            }
+        };
+
+        let mid = review.id.0 as i64;
+        let tid = thread.id.0 as i64;
+        if let Err(why) = sqlx::query!("UPDATE formanswers SET thread_id = ? WHERE message_id = ?", tid, mid)
+            .execute(&self.database)
+            .await
+        {
+            tracing::error!("Could not store review thread of {}: {:?}", mid, why);
+        }
+    }
+
+    /// Posts the outcome into the review thread and archives it.
+    pub async fn close_review_thread(&self, ctx: &Context, message_id: i64, summary: &str) {
+        let thread = match sqlx::query!("SELECT thread_id FROM formanswers WHERE message_id = ?", message_id)
+            .fetch_optional(&self.database)
+            .await
+        {
+            Ok(Some(row)) => match row.thread_id {
+                Some(id) => ChannelId(id as u64),
+                None => return,
+            },
+            _ => return,
+        };
+
+        let _ = thread
+            .send_message(ctx, |f| {
+                f.embed(|e| {
+                    e.title("Decision");
+                    e.description(summary);
+                    e.color(Color::DARK_GREY);
+                    e
+                })
+            })
+            .await;
+        if let Err(why) = thread.edit_thread(ctx, |t| t.archived(true)).await {
+            tracing::error!("Could not archive review thread {}: {:?}", thread.0, why);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    client::Context,
+    model::interactions::{
+        message_component::{ButtonStyle, MessageComponentInteraction},
+        InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
+    },
+    utils::Color,
+};
+
+use crate::bot::Bot;
+
+pub const WARNING_FIELD: &str = "⚠️ Answers need review";
+
+/// Youngest and oldest age we accept as a plausible answer.
+const MIN_AGE: u32 = 13;
+const MAX_AGE: u32 = 120;
+
+/// Whether a form question asks for the applicant's age.
+pub fn is_age_question(name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    name.contains("how old") || name == "age" || name.starts_with("age ") || name.starts_with("your age")
+}
+
+/// Reads an age out of answers like `17`, `17 years`, `about 25` or `17-18`.
+///
+/// Ranges resolve to their lower bound, so an applicant is never placed in an older bracket than they said.
+/// Fractions are dropped and numbers that can't be an age (`in 2 weeks`) are ignored.
+pub fn parse_age(text: &str) -> Option<u32> {
+    text.split(|c: char| !c.is_ascii_digit() && c != '.')
+        .filter_map(|n| n.split('.').next())
+        .filter_map(|n| n.parse::<u32>().ok())
+        .filter(|age| (MIN_AGE..=MAX_AGE).contains(age))
+        .min()
+}
+
+/// The (over 18, over 30) brackets for an age.
+pub fn age_brackets(age: u32) -> (bool, bool) {
+    (age >= 18, age >= 30)
+}
+
+/// Checks the answers for contradictions. An empty list means the submission can be approved as is.
+pub fn validate_answers(is_18_plus: bool, is_30_plus: bool, age: Option<&str>) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if is_30_plus && !is_18_plus {
+        warnings.push("Answered \"over 30\" but not \"over 18\"".to_string());
+    }
+
+    if let Some(answer) = age.map(str::trim).filter(|a| !a.is_empty()) {
+        match parse_age(answer) {
+            None => warnings.push(format!("Age answer `{}` is not a plausible age", answer)),
+            Some(age) => {
+                let (derived_18_plus, derived_30_plus) = age_brackets(age);
+                if derived_18_plus != is_18_plus {
+                    warnings.push(format!(
+                        "Age answer says {} but \"over 18\" was answered with {}",
+                        age,
+                        if is_18_plus { "Yes" } else { "No" }
+                    ));
+                }
+                if derived_30_plus != is_30_plus {
+                    warnings.push(format!(
+                        "Age answer says {} but \"over 30\" was answered with {}",
+                        age,
+                        if is_30_plus { "Yes" } else { "No" }
+                    ));
+                }
+            }
+        }
+    }
+
+    warnings
+}
+
+/// The review buttons. Accept stays disabled while the age bracket is unresolved.
+pub fn review_components(c: &mut CreateComponents, approval_blocked: bool) -> &mut CreateComponents {
+    c.create_action_row(|a| {
+        a.create_button(|b| {
+            b.label("Accept");
+            b.style(ButtonStyle::Success);
+            b.custom_id("approve_user");
+            b.disabled(approval_blocked);
+            b
+        });
+        a.create_button(|b| {
+            b.label("Deny & Ban");
+            b.style(ButtonStyle::Danger);
+            b.custom_id("reject_user_and_ban");
+            b
+        });
+        a.create_button(|b| {
+            b.label("Deny & Kick");
+            b.style(ButtonStyle::Danger);
+            b.custom_id("reject_user_and_kick");
+            b
+        });
+        a.create_button(|b| {
+            b.label("Add note");
+            b.style(ButtonStyle::Secondary);
+            b.custom_id("add_note");
+            b
+        })
+    });
+
+    if approval_blocked {
+        bracket_row(c);
+    }
+
+    c
+}
+
+/// Buttons that let a moderator settle the age bracket of a flagged submission.
+pub fn bracket_row(c: &mut CreateComponents) -> &mut CreateComponents {
+    c.create_action_row(|a| {
+        a.create_button(|b| {
+            b.label("Set bracket: under 18");
+            b.style(ButtonStyle::Secondary);
+            b.custom_id("resolve_bracket_minor");
+            b
+        });
+        a.create_button(|b| {
+            b.label("Set bracket: 18 to 29");
+            b.style(ButtonStyle::Secondary);
+            b.custom_id("resolve_bracket_adult");
+            b
+        });
+        a.create_button(|b| {
+            b.label("Set bracket: 30+");
+            b.style(ButtonStyle::Secondary);
+            b.custom_id("resolve_bracket_senior");
+            b
+        })
+    })
+}
+
+impl Bot {
+    pub async fn resolve_bracket_button(&self, ctx: &Context, msgc: &mut MessageComponentInteraction) {
+        let (label, is_18_plus, is_30_plus) = match msgc.data.custom_id.as_str() {
+            "resolve_bracket_minor" => ("under 18", false, false),
+            "resolve_bracket_adult" => ("18 to 29", true, false),
+            _ => ("30+", true, true),
+        };
+
+        let mid = msgc.message.id.0 as i64;
+        let updated = sqlx::query!(
+            "UPDATE formanswers SET is_18_plus = ?, is_30_plus = ?, validation_warnings = NULL WHERE message_id = ? AND status = 'pending'",
+            is_18_plus,
+            is_30_plus,
+            mid
+        )
+        .execute(&self.database)
+        .await;
+
+        if !matches!(updated, Ok(ref r) if r.rows_affected() > 0) {
+            let _ = msgc
+                .create_interaction_response(ctx, |f| {
+                    f.kind(InteractionResponseType::ChannelMessageWithSource);
+                    f.interaction_response_data(|f| {
+                        f.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+                        f.embed(|e| {
+                            e.title("Error");
+                            e.description("This submission is no longer waiting for review");
+                            e.color(Color::DARK_RED);
+                            e
+                        })
+                    })
+                })
+                .await;
+            return;
+        }
+
+        let is_update = sqlx::query!("SELECT kind FROM formanswers WHERE message_id = ?", mid)
+            .fetch_one(&self.database)
+            .await
+            .map(|r| r.kind == "update")
+            .unwrap_or(false);
+
+        let mut embed = CreateEmbed::from(msgc.message.embeds[0].clone());
+        embed.field(
+            "Age bracket resolved",
+            format!("Set to {} by {}", label, msgc.user.tag()),
+            false,
+        );
+        crate::embeds::fit(&mut embed);
+
+        let _ = msgc
+            .create_interaction_response(ctx, |f| {
+                f.kind(InteractionResponseType::UpdateMessage);
+                f.interaction_response_data(|f| {
+                    f.set_embed(embed);
+                    f.components(|c| {
+                        if is_update {
+                            crate::reverify::update_components(c, false)
+                        } else {
+                            review_components(c, false)
+                        }
+                    })
+                })
+            })
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_age;
+
+    #[test]
+    fn parses_plain_ages() {
+        assert_eq!(parse_age("17"), Some(17));
+        assert_eq!(parse_age("17 years"), Some(17));
+        assert_eq!(parse_age("about 25."), Some(25));
+    }
+
+    #[test]
+    fn ranges_use_lower_bound() {
+        assert_eq!(parse_age("17-18"), Some(17));
+    }
+
+    #[test]
+    fn drops_fractions() {
+        assert_eq!(parse_age("17.5"), Some(17));
+    }
+
+    #[test]
+    fn ignores_implausible_numbers() {
+        assert_eq!(parse_age("25 (turning 26 in 2 weeks)"), Some(25));
+        assert_eq!(parse_age("5"), None);
+        assert_eq!(parse_age("200"), None);
+        assert_eq!(parse_age("old enough"), None);
+    }
+}
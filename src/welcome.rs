@@ -0,0 +1,66 @@
+use serenity::{
+    client::Context,
+    model::{
+        id::{GuildId, RoleId},
+        prelude::User,
+    },
+    prelude::Mentionable,
+};
+
+use crate::bot::Bot;
+
+pub const DEFAULT_TEMPLATE: &str =
+    "Welcome to {server}, {user}! Your application was approved and you were given the following roles: {roles}";
+
+/// Posted to the welcome channel. Never lists roles, those stay in the DM.
+pub const DEFAULT_CHANNEL_TEMPLATE: &str = "Welcome to {server}, {mention}!";
+
+pub fn render_template(template: &str, user: &str, server: &str, roles: &str) -> String {
+    template
+        .replace("{user}", user)
+        .replace("{server}", server)
+        .replace("{roles}", roles)
+}
+
+pub fn render_channel_template(template: &str, mention: &str, server: &str) -> String {
+    template.replace("{mention}", mention).replace("{server}", server)
+}
+
+impl Bot {
+    /// Greets a freshly approved member. Returns `false` if the DM could not be delivered.
+    pub async fn welcome_member(&self, ctx: &Context, guild_id: GuildId, user: &User, granted: &[RoleId]) -> bool {
+        let (server, roles) = match guild_id.to_partial_guild(&ctx.http).await {
+            Ok(guild) => {
+                let roles = granted
+                    .iter()
+                    .filter_map(|id| guild.roles.get(id))
+                    .map(|r| r.name.clone())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                (guild.name, roles)
+            }
+            Err(why) => {
+                tracing::error!("Could not fetch guild {}: {:?}", guild_id, why);
+                ("the server".to_string(), String::new())
+            }
+        };
+
+        let mention = user.mention().to_string();
+        let text = render_template(&self.welcome.dm_template, &mention, &server, &roles);
+
+        if let Some(channel) = self.welcome.channel {
+            let public = render_channel_template(&self.welcome.channel_template, &mention, &server);
+            if let Err(why) = channel.say(&ctx.http, &public).await {
+                tracing::error!("Could not post welcome message: {:?}", why);
+            }
+        }
+
+        match user.direct_message(ctx, |m| m.content(&text)).await {
+            Ok(_) => true,
+            Err(why) => {
+                tracing::warn!("Could not DM {}: {:?}", user.tag(), why);
+                false
+            }
+        }
+    }
+}